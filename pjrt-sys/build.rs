@@ -26,6 +26,16 @@ fn main() {
     // gen protobuf
     prost_build::Config::new()
         .include_file("protos.rs")
-        .compile_protos(&[protos.join("xla/pjrt/compile_options.proto")], &[protos])
+        // maps encode in key order, equal messages encode to equal bytes
+        .btree_map(["."])
+        .file_descriptor_set_path(out_dir.join("protos.bin"))
+        .compile_protos(
+            &[
+                protos.join("xla/pjrt/compile_options.proto"),
+                protos.join("tsl/profiler/protobuf/profiler_options.proto"),
+                protos.join("tsl/profiler/protobuf/xplane.proto"),
+            ],
+            &[protos],
+        )
         .expect("unable to compile protos");
 }
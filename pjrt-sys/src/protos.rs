@@ -1 +1,4 @@
 include!(concat!(env!("OUT_DIR"), "/protos.rs"));
+
+/// Encoded `FileDescriptorSet` of the compiled protos.
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/protos.bin"));
@@ -508,3 +508,104 @@ impl_new!(
 );
 
 impl_new!(PJRT_Compile_Args, PJRT_Compile_Args_STRUCT_SIZE);
+
+impl_new!(PJRT_Profiler_Extension, PJRT_Profiler_Extension_STRUCT_SIZE);
+
+impl_new!(
+    PLUGIN_Profiler_Error_Destroy_Args,
+    PLUGIN_Profiler_Error_Destroy_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Error_Message_Args,
+    PLUGIN_Profiler_Error_Message_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Error_GetCode_Args,
+    PLUGIN_Profiler_Error_GetCode_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Create_Args,
+    PLUGIN_Profiler_Create_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Destroy_Args,
+    PLUGIN_Profiler_Destroy_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Start_Args,
+    PLUGIN_Profiler_Start_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_Stop_Args,
+    PLUGIN_Profiler_Stop_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PLUGIN_Profiler_CollectData_Args,
+    PLUGIN_Profiler_CollectData_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_Layouts_MemoryLayout_Destroy_Args,
+    PJRT_Layouts_MemoryLayout_Destroy_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_Layouts_MemoryLayout_Serialize_Args,
+    PJRT_Layouts_MemoryLayout_Serialize_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_Layouts_PJRT_Client_GetDefaultLayout_Args,
+    PJRT_Layouts_PJRT_Client_GetDefaultLayout_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_Layouts_PJRT_Buffer_MemoryLayout_Args,
+    PJRT_Layouts_PJRT_Buffer_MemoryLayout_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_FFI_TypeID_Register_Args,
+    PJRT_FFI_TypeID_Register_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_FFI_UserData_Add_Args,
+    PJRT_FFI_UserData_Add_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    PJRT_FFI_Register_Handler_Args,
+    PJRT_FFI_Register_Handler_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    XLA_FFI_Error_Create_Args,
+    XLA_FFI_Error_Create_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    XLA_FFI_Error_GetMessage_Args,
+    XLA_FFI_Error_GetMessage_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    XLA_FFI_Error_Destroy_Args,
+    XLA_FFI_Error_Destroy_Args_STRUCT_SIZE
+);
+
+impl_new!(
+    XLA_FFI_ExecutionContext_Get_Args,
+    XLA_FFI_ExecutionContext_Get_Args_STRUCT_SIZE
+);
+
+impl_new!(XLA_FFI_Api_Version, XLA_FFI_Api_Version_STRUCT_SIZE);
+
+impl_new!(XLA_FFI_Metadata, XLA_FFI_Metadata_STRUCT_SIZE);
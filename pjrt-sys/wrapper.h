@@ -1 +1,6 @@
+#include "dlpack/dlpack.h"
+#include "xla/ffi/api/c_api.h"
 #include "xla/pjrt/c/pjrt_c_api.h"
+#include "xla/pjrt/c/pjrt_c_api_ffi_extension.h"
+#include "xla/pjrt/c/pjrt_c_api_layouts_extension.h"
+#include "xla/pjrt/c/pjrt_c_api_profiler_extension.h"
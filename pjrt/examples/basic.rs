@@ -22,7 +22,7 @@ fn main() -> Result<()> {
 
     let result = loaded_executable.execution(inputs).run_sync()?;
 
-    let ouput = &result[0][0];
+    let ouput = result[0].leaves()[0];
     let output = ouput.to_host_sync().copy()?;
     println!("output= {:?}", output);
 
@@ -0,0 +1,28 @@
+use pjrt::{self, Client, HostBuffer, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let api = pjrt::plugin("pjrt_c_api_cpu_plugin.so").load()?;
+    let client = Client::builder(&api).build()?;
+
+    let host_buf = HostBuffer::from_data([1.0f32, 2.0, 3.0, 4.0])
+        .dims([2, 2])
+        .build();
+    let dev_buf = host_buf.to(&client).copy().await?;
+
+    // the tensor keeps the buffer alive, the view calls its deleter on drop
+    let tensor = dev_buf.to_dlpack().await?;
+    let view = unsafe { client.from_dlpack(tensor)? };
+    println!("view {:?}, {:?}", view.dims(), view.layout());
+
+    let b = view.to_host().copy().await?;
+    println!("to_host {:?}", b);
+
+    // scalars have no shape
+    let scalar = HostBuffer::from_scalar(1.25f32).to_sync(&client).copy()?;
+    let tensor = scalar.to_dlpack_sync()?;
+    let view = unsafe { client.from_dlpack(tensor)? };
+    println!("scalar {:?}", view.to_host_sync().copy()?);
+
+    Ok(())
+}
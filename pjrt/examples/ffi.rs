@@ -0,0 +1,51 @@
+use pjrt::ProgramFormat::MLIR;
+use pjrt::{
+    self, Client, Error, FfiAttrValue, FfiCallFrame, FfiExtension, FfiHandler, HostBuffer,
+    LoadedExecutable, Result,
+};
+
+const CODE: &[u8] = include_bytes!("prog_ffi.mlir");
+
+// multiplies its operand by the `scale` attribute
+struct Scale;
+
+impl FfiHandler for Scale {
+    fn execute(call_frame: &mut FfiCallFrame<'_>) -> Result<()> {
+        let scale = match call_frame.attr("scale")? {
+            Some(FfiAttrValue::F32(scale)) => scale,
+            _ => return Err(Error::FfiError("missing f32 attribute scale".to_string())),
+        };
+        let args = call_frame.args()?;
+        let input = args[0].as_slice::<f32>()?;
+        let mut rets = call_frame.rets()?;
+        let output = rets[0].as_mut_slice::<f32>()?;
+        for (o, i) in output.iter_mut().zip(input) {
+            *o = i * scale;
+        }
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    let api = pjrt::plugin("pjrt_c_api_cpu_plugin.so").load()?;
+    let ffi = api
+        .extension::<FfiExtension>()
+        .ok_or(Error::ExtensionNotFound("ffi"))?;
+    // handlers must be registered before compiling programs calling them
+    ffi.register_handler::<Scale>("rust_scale", "Host")?;
+
+    let client = Client::builder(&api).build()?;
+    let program = pjrt::Program::new(MLIR, CODE);
+    let loaded_executable = LoadedExecutable::builder(&client, &program).build()?;
+
+    let a = HostBuffer::from_data(vec![1.0f32, 2.0, 3.0, 4.0]).build();
+    println!("input = {:?}", a);
+
+    let inputs = a.to_sync(&client).copy()?;
+    let result = loaded_executable.execution(inputs).run_sync()?;
+
+    let output = result[0].leaves()[0].to_host_sync().copy()?;
+    println!("output = {:?}", output);
+
+    Ok(())
+}
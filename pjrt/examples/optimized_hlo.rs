@@ -0,0 +1,31 @@
+use pjrt::ProgramFormat::MLIR;
+use pjrt::{self, Client, LoadedExecutable, Program, Result};
+
+const CODE: &[u8] = include_bytes!("prog_f32.mlir");
+
+fn main() -> Result<()> {
+    let api = pjrt::plugin("pjrt_c_api_cpu_plugin.so").load()?;
+    let client = Client::builder(&api).build()?;
+
+    let program = Program::new(MLIR, CODE);
+    let loaded_executable = LoadedExecutable::builder(&client, &program).build()?;
+
+    let module = loaded_executable.executable().optimized_hlo_module()?;
+    println!("module = {}", module.name);
+    for computation in module.computations.iter() {
+        println!("computation {}", computation.name);
+        for instruction in computation.instructions.iter() {
+            println!("  {} = {}", instruction.name, instruction.opcode);
+        }
+    }
+
+    // the optimized module compiles again as is
+    let program = Program::from_hlo_module(module);
+    let recompiled = LoadedExecutable::builder(&client, &program).build()?;
+    println!(
+        "recompiled outputs = {:?}",
+        recompiled.executable().output_dims()
+    );
+
+    Ok(())
+}
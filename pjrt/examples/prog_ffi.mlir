@@ -0,0 +1,6 @@
+module {
+    func.func @main(%arg0: tensor<4xf32>) -> tensor<4xf32> {
+        %0 = stablehlo.custom_call @rust_scale(%arg0) {api_version = 4 : i32, backend_config = {scale = 2.000000e+00 : f32}} : (tensor<4xf32>) -> tensor<4xf32>
+        return %0 : tensor<4xf32>
+    }
+}
@@ -4,15 +4,16 @@ use std::sync::Arc;
 use pjrt_sys::{
     PJRT_Api, PJRT_Api_Version, PJRT_Client_Create_Args, PJRT_Compile_Args, PJRT_Error,
     PJRT_Error_Destroy_Args, PJRT_Error_GetCode_Args, PJRT_Error_Message_Args,
-    PJRT_ExecuteContext_Create_Args, PJRT_NamedValue, PJRT_Plugin_Attributes_Args,
-    PJRT_Plugin_Initialize_Args, PJRT_Program, PJRT_TopologyDescription_Create_Args,
+    PJRT_ExecuteContext_Create_Args, PJRT_Extension_Base, PJRT_Extension_Type, PJRT_NamedValue,
+    PJRT_Plugin_Attributes_Args, PJRT_Plugin_Initialize_Args, PJRT_Program,
+    PJRT_TopologyDescription_Create_Args,
 };
 
 use crate::kv_store::{kv_get_callback, kv_put_callback};
 use crate::named_value::NamedValueMap;
 use crate::{
-    utils, Client, CompileOptions, CompileToExecutable, Error, Executable, ExecuteContext,
-    KeyValueStore, NamedValue, Program, Result, TopologyDescription,
+    utils, ApiExtension, Client, CompileOptions, CompileToExecutable, Error, Executable,
+    ExecuteContext, Extension, KeyValueStore, NamedValue, Program, Result, TopologyDescription,
 };
 
 #[derive(Clone)]
@@ -49,6 +50,34 @@ impl Api {
         utils::to_named_value_map(args.attributes, args.num_attributes)
     }
 
+    pub fn extensions(&self) -> Vec<Extension> {
+        let mut extensions = vec![];
+        let mut ptr = self.raw.extension_start;
+        while !ptr.is_null() {
+            let base = unsafe { &*ptr };
+            extensions.push(Extension::new(base));
+            ptr = base.next;
+        }
+        extensions
+    }
+
+    fn find_extension(&self, ty: PJRT_Extension_Type) -> Option<*mut PJRT_Extension_Base> {
+        let mut ptr = self.raw.extension_start;
+        while !ptr.is_null() {
+            let base = unsafe { &*ptr };
+            if base.type_ == ty {
+                return Some(ptr);
+            }
+            ptr = base.next;
+        }
+        None
+    }
+
+    pub fn extension<T: ApiExtension>(&self) -> Option<T> {
+        self.find_extension(T::EXTENSION_TYPE)
+            .map(|ptr| T::wrap(self, ptr))
+    }
+
     pub fn create_execute_context(&self) -> Result<ExecuteContext> {
         let mut args = PJRT_ExecuteContext_Create_Args::new();
         args = self.PJRT_ExecuteContext_Create(args)?;
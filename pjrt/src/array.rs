@@ -0,0 +1,75 @@
+use ndarray::{ArrayBase, ArrayD, ArrayView, Data, Dimension, IxDyn, ShapeBuilder};
+
+use crate::{Buffer, ElemType, Error, HostBuffer, MemoryLayout, Result, Type, TypedHostBuffer};
+
+// ndarray only takes non negative strides in elements
+fn element_strides(layout: &MemoryLayout, dims: &[i64], elem_size: usize) -> Result<Vec<usize>> {
+    // host data is never tiled, only the dims order of a device layout applies
+    let strides = match layout {
+        MemoryLayout::Tiled(layout) => MemoryLayout::from_tiled(layout.minor_to_major.clone())
+            .build()
+            .element_strides(dims, elem_size)?,
+        layout => layout.element_strides(dims, elem_size)?,
+    };
+    strides
+        .into_iter()
+        .map(|stride| {
+            usize::try_from(stride).map_err(|_| {
+                Error::InvalidMemoryLayout(format!("negative stride {} in {:?}", stride, layout))
+            })
+        })
+        .collect()
+}
+
+impl<T: Type> TypedHostBuffer<T> {
+    /// Copies the buffer into an array, following its layout.
+    pub fn to_ndarray(&self) -> Result<ArrayD<T::ElemType>> {
+        let shape = self.dims().iter().map(|d| *d as usize).collect::<Vec<_>>();
+        let strides = element_strides(self.layout(), self.dims(), T::SIZE)?;
+        let view = ArrayView::from_shape(IxDyn(&shape).strides(IxDyn(&strides)), self.data())
+            .map_err(|err| Error::InvalidMemoryLayout(err.to_string()))?;
+        Ok(view.to_owned())
+    }
+
+    /// Copies `array` into a row major buffer.
+    pub fn from_ndarray<S, D>(array: &ArrayBase<S, D>) -> Self
+    where
+        S: Data<Elem = T::ElemType>,
+        D: Dimension,
+    {
+        let dims = array.shape().iter().map(|d| *d as i64).collect::<Vec<_>>();
+        let data = array.iter().cloned().collect::<Vec<_>>();
+        Self::from_data(data).dims(dims).build()
+    }
+}
+
+impl HostBuffer {
+    pub fn to_ndarray<E: ElemType>(&self) -> Result<ArrayD<E>> {
+        self.as_typed::<E::Type>()
+            .ok_or(Error::ElemTypeMismatch {
+                expected: E::Type::PRIMITIVE_TYPE,
+                actual: self.primitive_type(),
+            })?
+            .to_ndarray()
+    }
+
+    pub fn from_ndarray<E, S, D>(array: &ArrayBase<S, D>) -> Self
+    where
+        E: ElemType,
+        S: Data<Elem = E>,
+        D: Dimension,
+        Self: From<TypedHostBuffer<E::Type>>,
+    {
+        Self::from(TypedHostBuffer::<E::Type>::from_ndarray(array))
+    }
+}
+
+impl Buffer {
+    pub async fn to_ndarray<E: ElemType>(&self) -> Result<ArrayD<E>> {
+        self.to_host().copy().await?.to_ndarray()
+    }
+
+    pub fn to_ndarray_sync<E: ElemType>(&self) -> Result<ArrayD<E>> {
+        self.to_host_sync().copy()?.to_ndarray()
+    }
+}
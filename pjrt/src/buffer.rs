@@ -1,22 +1,37 @@
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
 use bon::bon;
 use pjrt_sys::{
     PJRT_Buffer, PJRT_Buffer_CopyToDevice_Args, PJRT_Buffer_CopyToMemory_Args,
-    PJRT_Buffer_Delete_Args, PJRT_Buffer_Destroy_Args, PJRT_Buffer_Device_Args,
-    PJRT_Buffer_Dimensions_Args, PJRT_Buffer_DynamicDimensionIndices_Args,
-    PJRT_Buffer_ElementType_Args, PJRT_Buffer_GetMemoryLayout_Args, PJRT_Buffer_IsDeleted_Args,
-    PJRT_Buffer_IsOnCpu_Args, PJRT_Buffer_MemoryLayout, PJRT_Buffer_Memory_Args,
-    PJRT_Buffer_OnDeviceSizeInBytes_Args, PJRT_Buffer_ReadyEvent_Args,
+    PJRT_Buffer_DecreaseExternalReferenceCount_Args, PJRT_Buffer_Delete_Args,
+    PJRT_Buffer_Destroy_Args, PJRT_Buffer_Device_Args, PJRT_Buffer_Dimensions_Args,
+    PJRT_Buffer_DynamicDimensionIndices_Args, PJRT_Buffer_ElementType_Args,
+    PJRT_Buffer_GetMemoryLayout_Args, PJRT_Buffer_IncreaseExternalReferenceCount_Args,
+    PJRT_Buffer_IsDeleted_Args, PJRT_Buffer_IsOnCpu_Args, PJRT_Buffer_MemoryLayout,
+    PJRT_Buffer_Memory_Args, PJRT_Buffer_OnDeviceSizeInBytes_Args,
+    PJRT_Buffer_OpaqueDeviceMemoryDataPointer_Args, PJRT_Buffer_ReadyEvent_Args,
     PJRT_Buffer_ToHostBuffer_Args, PJRT_Buffer_UnpaddedDimensions_Args,
+    PJRT_Buffer_UnsafePointer_Args,
 };
 
 use crate::event::Event;
-use crate::{Client, Device, HostBuffer, Memory, MemoryLayout, PrimitiveType, Result};
+use crate::host_buffer::{self, HostBufferCopyToDest, HostBufferSemantics};
+use crate::{
+    Client, Device, ElemType, HostBuffer, Memory, MemoryLayout, PrimitiveType, Result, Type,
+};
 
 pub struct Buffer {
     client: Client,
     pub(crate) ptr: *mut PJRT_Buffer,
 }
 
+// PJRT buffers may be used from any thread, the runtime synchronizes access to
+// the device memory with the buffer's usage events.
+unsafe impl Send for Buffer {}
+unsafe impl Sync for Buffer {}
+
 impl Drop for Buffer {
     fn drop(&mut self) {
         let mut args = PJRT_Buffer_Destroy_Args::new();
@@ -28,6 +43,127 @@ impl Drop for Buffer {
     }
 }
 
+/// A [`Buffer`] that may alias host memory borrowed for `'a`.
+///
+/// The memory stays borrowed until the runtime is done with it. Executions
+/// take the buffer by reference and complete before returning their outputs,
+/// so dropping the buffer after them returns right away. Dropping it while an execution still reads it, e.g. by
+/// cancelling the execution's future, blocks until the execution is done;
+/// [`BorrowedBuffer::release`] waits without blocking.
+pub struct BorrowedBuffer<'a> {
+    // only taken when dropped or released
+    buffer: Option<Buffer>,
+    done_with_host: Option<Event>,
+    _data: PhantomData<&'a [u8]>,
+}
+
+impl Drop for BorrowedBuffer<'_> {
+    fn drop(&mut self) {
+        // the runtime releases the host memory once the buffer and its pending
+        // usages are gone, the borrow can't end before
+        drop(self.buffer.take());
+        if let Some(event) = self.done_with_host.take() {
+            let _ = event.wait();
+        }
+    }
+}
+
+impl Deref for BorrowedBuffer<'_> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        self.buffer.as_ref().expect("buffer taken on drop")
+    }
+}
+
+#[bon]
+impl<'a> BorrowedBuffer<'a> {
+    fn borrow<E, D>(
+        data: *const c_void,
+        len: usize,
+        dest: &D,
+        dims: Option<Vec<i64>>,
+        semantics: HostBufferSemantics,
+    ) -> Result<Self>
+    where
+        E: ElemType,
+        D: HostBufferCopyToDest,
+    {
+        let dims = host_buffer::slice_dims(dims, len)?;
+        let args = host_buffer::buffer_from_host(
+            dest,
+            data,
+            E::Type::PRIMITIVE_TYPE,
+            &dims,
+            None,
+            None,
+            semantics,
+        )?;
+        let client = dest.client();
+        let done_with_host = (!args.done_with_host_buffer.is_null())
+            .then(|| Event::wrap(client.api(), args.done_with_host_buffer));
+        let buffer = Self {
+            buffer: Some(Buffer::wrap(client, args.buffer)),
+            done_with_host,
+            _data: PhantomData,
+        };
+        buffer.ready_event()?.wait()?;
+        Ok(buffer)
+    }
+
+    /// Drops the buffer, then waits until the runtime is done with the host
+    /// memory without blocking the thread.
+    pub async fn release(mut self) -> Result<()> {
+        drop(self.buffer.take());
+        match self.done_with_host.as_mut() {
+            Some(event) => event.await,
+            None => Ok(()),
+        }
+    }
+
+    /// Creates a buffer aliasing `data` where the platform allows it (cpu),
+    /// otherwise `data` is copied. The runtime never writes to `data`.
+    #[builder(finish_fn = build)]
+    pub fn from_slice<E, D>(
+        #[builder(start_fn)] data: &'a [E],
+        #[builder(start_fn)] dest: &D,
+        #[builder(into)] dims: Option<Vec<i64>>,
+    ) -> Result<Self>
+    where
+        E: ElemType,
+        D: HostBufferCopyToDest,
+    {
+        Self::borrow::<E, D>(
+            data.as_ptr() as *const c_void,
+            data.len(),
+            dest,
+            dims,
+            HostBufferSemantics::ImmutableZeroCopy,
+        )
+    }
+
+    /// Creates a buffer aliasing `data` where the platform allows it (cpu),
+    /// otherwise `data` is copied. The runtime may write to `data`.
+    #[builder(finish_fn = build)]
+    pub fn from_mut_slice<E, D>(
+        #[builder(start_fn)] data: &'a mut [E],
+        #[builder(start_fn)] dest: &D,
+        #[builder(into)] dims: Option<Vec<i64>>,
+    ) -> Result<Self>
+    where
+        E: ElemType,
+        D: HostBufferCopyToDest,
+    {
+        Self::borrow::<E, D>(
+            data.as_mut_ptr() as *const c_void,
+            data.len(),
+            dest,
+            dims,
+            HostBufferSemantics::MutableZeroCopy,
+        )
+    }
+}
+
 #[bon]
 impl Buffer {
     pub(crate) fn wrap(client: &Client, ptr: *mut PJRT_Buffer) -> Self {
@@ -43,109 +179,115 @@ impl Buffer {
     }
 
     pub fn primitive_type(&self) -> PrimitiveType {
+        self.try_primitive_type().expect("PJRT_Buffer_ElementType")
+    }
+
+    pub fn try_primitive_type(&self) -> Result<PrimitiveType> {
         let mut args = PJRT_Buffer_ElementType_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_ElementType(args)
-            .expect("PJRT_Buffer_ElementType");
-        PrimitiveType::try_from(args.type_).expect("PrimitiveType")
+        args = self.client.api().PJRT_Buffer_ElementType(args)?;
+        PrimitiveType::try_from(args.type_)
     }
 
     pub fn dims(&self) -> Vec<i64> {
+        self.try_dims().expect("PJRT_Buffer_Dimensions")
+    }
+
+    pub fn try_dims(&self) -> Result<Vec<i64>> {
         let mut args = PJRT_Buffer_Dimensions_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_Dimensions(args)
-            .expect("PJRT_Buffer_Dimensions");
+        args = self.client.api().PJRT_Buffer_Dimensions(args)?;
         if args.num_dims == 0 {
-            return vec![];
+            return Ok(vec![]);
         }
         let s = unsafe { std::slice::from_raw_parts(args.dims, args.num_dims) };
-        s.to_owned()
+        Ok(s.to_owned())
     }
 
     pub fn unpadded_dims(&self) -> Vec<i64> {
+        self.try_unpadded_dims()
+            .expect("PJRT_Buffer_UnpaddedDimensions")
+    }
+
+    pub fn try_unpadded_dims(&self) -> Result<Vec<i64>> {
         let mut args = PJRT_Buffer_UnpaddedDimensions_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_UnpaddedDimensions(args)
-            .expect("PJRT_Buffer_UnpaddedDimensions");
+        args = self.client.api().PJRT_Buffer_UnpaddedDimensions(args)?;
         let s = unsafe { std::slice::from_raw_parts(args.unpadded_dims, args.num_dims) };
-        s.to_owned()
+        Ok(s.to_owned())
     }
 
     pub fn dynamic_dims_indices(&self) -> Vec<usize> {
+        self.try_dynamic_dims_indices()
+            .expect("PJRT_Buffer_DynamicDimensionIndices")
+    }
+
+    pub fn try_dynamic_dims_indices(&self) -> Result<Vec<usize>> {
         let mut args = PJRT_Buffer_DynamicDimensionIndices_Args::new();
         args.buffer = self.ptr;
         args = self
             .client
             .api()
-            .PJRT_Buffer_DynamicDimensionIndices(args)
-            .expect("PJRT_Buffer_DynamicDimensionIndices");
+            .PJRT_Buffer_DynamicDimensionIndices(args)?;
         let s =
             unsafe { std::slice::from_raw_parts(args.dynamic_dim_indices, args.num_dynamic_dims) };
-        s.to_owned()
+        Ok(s.to_owned())
     }
 
     pub fn layout(&self) -> MemoryLayout {
+        self.try_layout().expect("PJRT_Buffer_GetMemoryLayout")
+    }
+
+    pub fn try_layout(&self) -> Result<MemoryLayout> {
         let mut args = PJRT_Buffer_GetMemoryLayout_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_GetMemoryLayout(args)
-            .expect("PJRT_Buffer_GetMemoryLayout");
-        MemoryLayout::try_from(&args.layout).expect("layout")
+        args = self.client.api().PJRT_Buffer_GetMemoryLayout(args)?;
+        MemoryLayout::try_from(&args.layout)
     }
 
     pub fn on_device_size(&self) -> usize {
+        self.try_on_device_size()
+            .expect("PJRT_Buffer_OnDeviceSizeInBytes")
+    }
+
+    pub fn try_on_device_size(&self) -> Result<usize> {
         let mut args = PJRT_Buffer_OnDeviceSizeInBytes_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_OnDeviceSizeInBytes(args)
-            .expect("PJRT_Buffer_GetMemoryLayout");
-        args.on_device_size_in_bytes
+        args = self.client.api().PJRT_Buffer_OnDeviceSizeInBytes(args)?;
+        Ok(args.on_device_size_in_bytes)
     }
 
     pub fn is_on_cpu(&self) -> bool {
+        self.try_is_on_cpu().expect("PJRT_Buffer_IsOnCpu")
+    }
+
+    pub fn try_is_on_cpu(&self) -> Result<bool> {
         let mut args = PJRT_Buffer_IsOnCpu_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_IsOnCpu(args)
-            .expect("PJRT_Buffer_IsOnCpu");
-        args.is_on_cpu
+        args = self.client.api().PJRT_Buffer_IsOnCpu(args)?;
+        Ok(args.is_on_cpu)
     }
 
     pub fn device(&self) -> Device {
+        self.try_device().expect("PJRT_Buffer_Device")
+    }
+
+    pub fn try_device(&self) -> Result<Device> {
         let mut args = PJRT_Buffer_Device_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_Device(args)
-            .expect("PJRT_Buffer_Device");
-        Device::wrap(&self.client, args.device)
+        args = self.client.api().PJRT_Buffer_Device(args)?;
+        Ok(Device::wrap(&self.client, args.device))
     }
 
     pub fn memory(&self) -> Memory {
+        self.try_memory().expect("PJRT_Buffer_Memory")
+    }
+
+    pub fn try_memory(&self) -> Result<Memory> {
         let mut args = PJRT_Buffer_Memory_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_Memory(args)
-            .expect("PJRT_Buffer_Memory");
-        Memory::wrap(&self.client, args.memory)
+        args = self.client.api().PJRT_Buffer_Memory(args)?;
+        Ok(Memory::wrap(&self.client, args.memory))
     }
 
     pub fn delete(self) {
@@ -158,14 +300,14 @@ impl Buffer {
     }
 
     pub fn is_deleted(&self) -> bool {
+        self.try_is_deleted().expect("PJRT_Buffer_IsDeleted")
+    }
+
+    pub fn try_is_deleted(&self) -> Result<bool> {
         let mut args = PJRT_Buffer_IsDeleted_Args::new();
         args.buffer = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Buffer_IsDeleted(args)
-            .expect("PJRT_Buffer_IsDeleted");
-        args.is_deleted
+        args = self.client.api().PJRT_Buffer_IsDeleted(args)?;
+        Ok(args.is_deleted)
     }
 
     pub(crate) fn ready_event(&self) -> Result<Event> {
@@ -250,9 +392,12 @@ impl Buffer {
         let (args, data) = self.call_copy_to_host(host_layout.as_ref())?;
         let event = Event::wrap(self.client.api(), args.event);
         event.await?;
-        let ty = self.primitive_type();
-        let dims = self.dims();
-        let layout = host_layout.unwrap_or_else(|| self.layout());
+        let ty = self.try_primitive_type()?;
+        let dims = self.try_dims()?;
+        let layout = match host_layout {
+            Some(layout) => layout,
+            None => self.try_layout()?,
+        };
         HostBuffer::from_bytes(data, ty)
             .dims(dims)
             .layout(layout)
@@ -264,18 +409,73 @@ impl Buffer {
         let (args, data) = self.call_copy_to_host(host_layout.as_ref())?;
         let event = Event::wrap(self.client.api(), args.event);
         event.wait()?;
-        let ty = self.primitive_type();
-        let dims = self.dims();
-        let layout = host_layout.unwrap_or_else(|| self.layout());
+        let ty = self.try_primitive_type()?;
+        let dims = self.try_dims()?;
+        let layout = match host_layout {
+            Some(layout) => layout,
+            None => self.try_layout()?,
+        };
         HostBuffer::from_bytes(data, ty)
             .dims(dims)
             .layout(layout)
             .build()
     }
 
-    // TODO:
-    // PJRT_Buffer_UnsafePointer
-    // PJRT_Buffer_IncreaseExternalReferenceCount
-    // PJRT_Buffer_DecreaseExternalReferenceCount
-    // PJRT_Buffer_OpaqueDeviceMemoryDataPointer
+    /// Prevents PJRT from freeing, moving or donating the underlying device
+    /// memory until the returned guard is dropped.
+    pub fn external_ref(&self) -> Result<ExternalBufferRef<'_>> {
+        let mut args = PJRT_Buffer_IncreaseExternalReferenceCount_Args::new();
+        args.buffer = self.ptr;
+        self.client
+            .api()
+            .PJRT_Buffer_IncreaseExternalReferenceCount(args)?;
+        Ok(ExternalBufferRef { buffer: self })
+    }
+}
+
+/// An external reference to the device memory of a [`Buffer`].
+///
+/// The pointers it exposes stay valid for as long as the reference is alive.
+pub struct ExternalBufferRef<'a> {
+    buffer: &'a Buffer,
+}
+
+impl Drop for ExternalBufferRef<'_> {
+    fn drop(&mut self) {
+        let mut args = PJRT_Buffer_DecreaseExternalReferenceCount_Args::new();
+        args.buffer = self.buffer.ptr;
+        // panicking here would abort if already unwinding, and there is
+        // nothing left to release on failure
+        let _ = self
+            .buffer
+            .client
+            .api()
+            .PJRT_Buffer_DecreaseExternalReferenceCount(args);
+    }
+}
+
+impl<'a> ExternalBufferRef<'a> {
+    pub fn buffer(&self) -> &'a Buffer {
+        self.buffer
+    }
+
+    /// Platform-dependent address of the buffer, often but not always the
+    /// physical device address.
+    pub fn unsafe_pointer(&self) -> Result<usize> {
+        let mut args = PJRT_Buffer_UnsafePointer_Args::new();
+        args.buffer = self.buffer.ptr;
+        args = self.buffer.client.api().PJRT_Buffer_UnsafePointer(args)?;
+        Ok(args.buffer_pointer)
+    }
+
+    pub fn opaque_device_memory_data_pointer(&self) -> Result<*mut c_void> {
+        let mut args = PJRT_Buffer_OpaqueDeviceMemoryDataPointer_Args::new();
+        args.buffer = self.buffer.ptr;
+        args = self
+            .buffer
+            .client
+            .api()
+            .PJRT_Buffer_OpaqueDeviceMemoryDataPointer(args)?;
+        Ok(args.device_memory_ptr)
+    }
 }
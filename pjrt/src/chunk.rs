@@ -1,3 +1,5 @@
+use std::slice;
+
 use ::std::os::raw::c_void;
 use pjrt_sys::PJRT_Chunk;
 
@@ -17,6 +19,28 @@ impl Chunk {
     pub fn new(data: Vec<u8>) -> Self {
         Self { data }
     }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Copies the data out of a chunk handed over by PJRT and releases it.
+    pub(crate) unsafe fn from_raw(chunk: *mut PJRT_Chunk) -> Self {
+        let chunk = unsafe { &*chunk };
+        let data = if chunk.data.is_null() || chunk.size == 0 {
+            vec![]
+        } else {
+            unsafe { slice::from_raw_parts(chunk.data as *const u8, chunk.size) }.to_vec()
+        };
+        if let Some(deleter) = chunk.deleter {
+            unsafe { deleter(chunk.data, chunk.deleter_arg) };
+        }
+        Self { data }
+    }
 }
 
 impl From<Chunk> for PJRT_Chunk {
@@ -1,28 +1,45 @@
 use std::borrow::Cow;
-use std::rc::Rc;
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
 use std::slice;
+use std::sync::Arc;
 
 use bon::bon;
 use pjrt_sys::{
-    PJRT_Client, PJRT_Client_AddressableDevices_Args, PJRT_Client_AddressableMemories_Args,
-    PJRT_Client_Compile_Args, PJRT_Client_DefaultDeviceAssignment_Args, PJRT_Client_Destroy_Args,
-    PJRT_Client_Devices_Args, PJRT_Client_LookupAddressableDevice_Args,
+    PJRT_Buffer_MemoryLayout, PJRT_Buffer_Type, PJRT_Client, PJRT_Client_AddressableDevices_Args,
+    PJRT_Client_AddressableMemories_Args, PJRT_Client_Compile_Args,
+    PJRT_Client_CreateViewOfDeviceBuffer_Args, PJRT_Client_DefaultDeviceAssignment_Args,
+    PJRT_Client_Destroy_Args, PJRT_Client_Devices_Args, PJRT_Client_LookupAddressableDevice_Args,
     PJRT_Client_LookupDevice_Args, PJRT_Client_PlatformName_Args, PJRT_Client_PlatformVersion_Args,
     PJRT_Client_ProcessIndex_Args, PJRT_Client_TopologyDescription_Args,
     PJRT_Executable_DeserializeAndLoad_Args, PJRT_Program,
 };
 
 use crate::{
-    utils, Api, CompileOptions, CompileToLoadedExecutable, Device, DeviceAssignment,
-    GlobalDeviceId, KeyValueStore, LoadedExecutable, LocalHardwareId, Memory, NamedValue, Program,
-    Result, TopologyDescription,
+    utils, Api, Buffer, CompileOptions, CompileToLoadedExecutable, Device, DeviceAssignment, Error,
+    GlobalDeviceId, KeyValueStore, LayoutsExtension, LoadedExecutable, LocalHardwareId, Memory,
+    MemoryLayout, NamedValue, PrimitiveType, Program, Result, TopologyDescription,
 };
 
+/// Called by PJRT once a device buffer view no longer uses the memory it wraps.
+pub type OnDeleteCallback = Box<dyn FnOnce(*mut c_void) + Send + 'static>;
+
+unsafe extern "C" fn on_delete_callback(device_buffer_ptr: *mut c_void, user_arg: *mut c_void) {
+    let callback = unsafe { Box::from_raw(user_arg as *mut OnDeleteCallback) };
+    // unwinding across the FFI boundary is undefined behavior
+    let _ = panic::catch_unwind(AssertUnwindSafe(move || callback(device_buffer_ptr)));
+}
+
 struct ClientRaw {
     api: Api,
     ptr: *mut PJRT_Client,
 }
 
+// PJRT clients are thread safe: every PJRT_Client_* call may be made
+// concurrently, and the client is destroyed once, by the last owner.
+unsafe impl Send for ClientRaw {}
+unsafe impl Sync for ClientRaw {}
+
 impl Drop for ClientRaw {
     fn drop(&mut self) {
         let mut args = PJRT_Client_Destroy_Args::new();
@@ -35,7 +52,7 @@ impl Drop for ClientRaw {
 
 #[derive(Clone)]
 pub struct Client {
-    raw: Rc<ClientRaw>,
+    raw: Arc<ClientRaw>,
 }
 
 #[bon]
@@ -43,7 +60,7 @@ impl Client {
     pub(crate) fn wrap(api: &Api, ptr: *mut PJRT_Client) -> Self {
         assert!(!ptr.is_null());
         Self {
-            raw: Rc::new(ClientRaw {
+            raw: Arc::new(ClientRaw {
                 api: api.clone(),
                 ptr,
             }),
@@ -69,82 +86,97 @@ impl Client {
     }
 
     pub fn platform_name(&self) -> Cow<'_, str> {
+        self.try_platform_name().expect("PJRT_Client_PlatformName")
+    }
+
+    pub fn try_platform_name(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Client_PlatformName_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_PlatformName(args)
-            .expect("PJRT_Client_PlatformName");
-        utils::str_from_raw(args.platform_name, args.platform_name_size)
+        args = self.api().PJRT_Client_PlatformName(args)?;
+        Ok(utils::str_from_raw(
+            args.platform_name,
+            args.platform_name_size,
+        ))
     }
 
     pub fn platform_version(&self) -> Cow<'_, str> {
+        self.try_platform_version()
+            .expect("PJRT_Client_PlatformVersion")
+    }
+
+    pub fn try_platform_version(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Client_PlatformVersion_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_PlatformVersion(args)
-            .expect("PJRT_Client_PlatformVersion");
-        utils::str_from_raw(args.platform_version, args.platform_version_size)
+        args = self.api().PJRT_Client_PlatformVersion(args)?;
+        Ok(utils::str_from_raw(
+            args.platform_version,
+            args.platform_version_size,
+        ))
     }
 
     pub fn process_index(&self) -> i32 {
+        self.try_process_index().expect("PJRT_Client_ProcessIndex")
+    }
+
+    pub fn try_process_index(&self) -> Result<i32> {
         let mut args = PJRT_Client_ProcessIndex_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_ProcessIndex(args)
-            .expect("PJRT_Client_ProcessIndex");
-        args.process_index
+        args = self.api().PJRT_Client_ProcessIndex(args)?;
+        Ok(args.process_index)
     }
 
     pub fn devices(&self) -> Vec<Device> {
+        self.try_devices().expect("PJRT_Client_Devices")
+    }
+
+    pub fn try_devices(&self) -> Result<Vec<Device>> {
         let mut args = PJRT_Client_Devices_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_Devices(args)
-            .expect("PJRT_Client_Devices");
+        args = self.api().PJRT_Client_Devices(args)?;
         let raw_devices = unsafe { slice::from_raw_parts(args.devices, args.num_devices) };
-        raw_devices
+        Ok(raw_devices
             .iter()
             .cloned()
             .map(|d| Device::wrap(self, d))
-            .collect()
+            .collect())
     }
 
     pub fn addressable_devices(&self) -> Vec<Device> {
+        self.try_addressable_devices()
+            .expect("PJRT_Client_AddressableDevices")
+    }
+
+    pub fn try_addressable_devices(&self) -> Result<Vec<Device>> {
         let mut args = PJRT_Client_AddressableDevices_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_AddressableDevices(args)
-            .expect("PJRT_Client_AddressableDevices");
+        args = self.api().PJRT_Client_AddressableDevices(args)?;
         let devices = unsafe {
             slice::from_raw_parts(args.addressable_devices, args.num_addressable_devices)
         };
-        devices
+        Ok(devices
             .iter()
             .cloned()
             .map(|d| Device::wrap(self, d))
-            .collect()
+            .collect())
     }
 
     pub fn addressable_memories(&self) -> Vec<Memory> {
+        self.try_addressable_memories()
+            .expect("PJRT_Client_AddressableMemories")
+    }
+
+    pub fn try_addressable_memories(&self) -> Result<Vec<Memory>> {
         let mut args = PJRT_Client_AddressableMemories_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_AddressableMemories(args)
-            .expect("PJRT_Client_AddressableMemories");
+        args = self.api().PJRT_Client_AddressableMemories(args)?;
         let memories = unsafe {
             slice::from_raw_parts(args.addressable_memories, args.num_addressable_memories)
         };
-        memories
+        Ok(memories
             .iter()
             .cloned()
             .map(|d| Memory::wrap(self, d))
-            .collect()
+            .collect())
     }
 
     pub fn lookup_device(&self, global_device_id: GlobalDeviceId) -> Result<Device> {
@@ -197,17 +229,112 @@ impl Client {
     }
 
     pub fn topology(&self) -> TopologyDescription {
+        self.try_topology()
+            .expect("PJRT_Client_TopologyDescription")
+    }
+
+    pub fn try_topology(&self) -> Result<TopologyDescription> {
         let mut args = PJRT_Client_TopologyDescription_Args::new();
         args.client = self.ptr();
-        args = self
-            .api()
-            .PJRT_Client_TopologyDescription(args)
-            .expect("PJRT_Client_TopologyDescription");
-        TopologyDescription::wrap(self.api(), args.topology)
+        args = self.api().PJRT_Client_TopologyDescription(args)?;
+        Ok(TopologyDescription::wrap(self.api(), args.topology))
     }
 
-    // TODO:
-    // PJRT_Client_CreateViewOfDeviceBuffer
+    /// Default layout of a buffer of `ty` and `dims` in `memory`, one of this
+    /// client's memories. Requires the layouts extension.
+    pub fn default_layout(
+        &self,
+        ty: PrimitiveType,
+        dims: &[i64],
+        memory: &Memory,
+    ) -> Result<MemoryLayout> {
+        if memory.client().ptr() != self.ptr() {
+            return Err(Error::MemoryNotOnClient);
+        }
+        self.api()
+            .extension::<LayoutsExtension>()
+            .ok_or(Error::ExtensionNotFound("layouts"))?
+            .memory_default_layout(memory, ty, dims)
+    }
+
+    /// Creates a [`Buffer`] that is a non-owned view of device memory allocated
+    /// by another library, without copying it.
+    ///
+    /// # Safety
+    ///
+    /// `device_buffer_ptr` must point to memory on `dest` that holds `dims`
+    /// elements of `ty` laid out as `layout`, and must stay valid until
+    /// `on_delete` is called.
+    #[builder(finish_fn = build)]
+    pub unsafe fn create_view_of_device_buffer<D>(
+        &self,
+        #[builder(start_fn)] device_buffer_ptr: *mut c_void,
+        #[builder(start_fn)] ty: PrimitiveType,
+        #[builder(start_fn, into)] dims: Vec<i64>,
+        #[builder(start_fn)] dest: &D,
+        layout: Option<MemoryLayout>,
+        on_delete: Option<OnDeleteCallback>,
+        stream: Option<isize>,
+    ) -> Result<Buffer>
+    where
+        D: DeviceBufferViewDest,
+    {
+        let mut args = PJRT_Client_CreateViewOfDeviceBuffer_Args::new();
+        args.client = self.ptr();
+        args.device_buffer_ptr = device_buffer_ptr;
+        args.dims = dims.as_ptr();
+        args.num_dims = dims.len();
+        args.element_type = ty as PJRT_Buffer_Type;
+        let mut layout = layout.as_ref().map(PJRT_Buffer_MemoryLayout::from);
+        if let Some(layout) = layout.as_mut() {
+            args.layout = layout as *mut _;
+        }
+        if let Some(stream) = stream {
+            args.stream = stream;
+        }
+        dest.set_args(&mut args)?;
+        let on_delete = on_delete.map(|f| Box::into_raw(Box::new(f)));
+        if let Some(on_delete) = on_delete {
+            args.on_delete_callback = Some(on_delete_callback);
+            args.on_delete_callback_arg = on_delete as *mut c_void;
+        }
+        match self.api().PJRT_Client_CreateViewOfDeviceBuffer(args) {
+            Ok(args) => Ok(Buffer::wrap(self, args.buffer)),
+            Err(err) => {
+                // no view was created, so the callback will never be invoked
+                if let Some(on_delete) = on_delete {
+                    drop(unsafe { Box::from_raw(on_delete) });
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+pub(crate) trait DeviceBufferViewDest {
+    fn set_args(&self, args: &mut PJRT_Client_CreateViewOfDeviceBuffer_Args) -> Result<()>;
+}
+
+impl DeviceBufferViewDest for Device {
+    fn set_args(&self, args: &mut PJRT_Client_CreateViewOfDeviceBuffer_Args) -> Result<()> {
+        args.device = self.ptr;
+        Ok(())
+    }
+}
+
+impl DeviceBufferViewDest for Memory {
+    // views can only be placed on a device, so the memory must be the default
+    // memory of one of the devices that can address it
+    fn set_args(&self, args: &mut PJRT_Client_CreateViewOfDeviceBuffer_Args) -> Result<()> {
+        let id = self.try_id()?;
+        for device in self.try_addressable_by_devices()? {
+            if device.try_default_memory()?.try_id()? == id {
+                args.device = device.ptr;
+                return Ok(());
+            }
+        }
+        Err(Error::NoAddressableDevice)
+    }
 }
 
 impl CompileToLoadedExecutable<Program> for Client {
@@ -0,0 +1,322 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use bon::bon;
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::{Client, CompileOptions, LoadedExecutable, Program, Result};
+
+const EXTENSION: &str = "pjrt";
+const TMP_EXTENSION: &str = "tmp";
+// older files were left by writers that died before renaming them
+const STALE_TMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+// hashes the program, the options and the platform's fields
+fn cache_key(program: &Program, options: &CompileOptions, platform: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    // length prefixed, so that consecutive fields can't run into each other
+    let mut write = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    write(program.format().as_bytes());
+    write(program.code());
+    // protos encode maps sorted, equal options hash the same
+    write(&options.proto().encode_to_vec());
+    for field in platform {
+        write(field);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Stores compiled executables on disk, so that compiling the same program
+/// again only loads them.
+///
+/// Entries are keyed on the program, the compile options, the platform and
+/// the PJRT API version. Once the cache exceeds `max_size` bytes, the least
+/// recently used entries are removed.
+#[derive(Debug, Clone)]
+pub struct CompilationCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+#[bon]
+impl CompilationCache {
+    #[builder(finish_fn = build)]
+    pub fn builder(
+        #[builder(start_fn, into)] dir: PathBuf,
+        #[builder(default = 1 << 30)] max_size: u64,
+    ) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_size })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    /// The cache key of compiling `program` with `options` on `client`.
+    pub fn key(
+        &self,
+        client: &Client,
+        program: &Program,
+        options: &CompileOptions,
+    ) -> Result<String> {
+        let version = client.api().version();
+        Ok(cache_key(
+            program,
+            options,
+            &[
+                client.try_platform_name()?.as_bytes(),
+                client.try_platform_version()?.as_bytes(),
+                &version.major_version.to_le_bytes(),
+                &version.minor_version.to_le_bytes(),
+            ],
+        ))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension(EXTENSION)
+    }
+
+    /// Loads the executable stored under `key`. Entries that fail to load are
+    /// removed.
+    pub fn get(&self, client: &Client, key: &str) -> Option<LoadedExecutable> {
+        self.load(key, |bytes| client.load_executable(bytes))
+    }
+
+    fn load<T>(&self, key: &str, load: impl FnOnce(&[u8]) -> Result<T>) -> Option<T> {
+        let path = self.path(key);
+        let bytes = fs::read(&path).ok()?;
+        match load(&bytes) {
+            Ok(loaded) => {
+                // the modification time orders entries for eviction
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+                Some(loaded)
+            }
+            Err(_) => {
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores `executable` under `key`, then evicts entries beyond the size
+    /// limit.
+    pub fn put(&self, key: &str, executable: &LoadedExecutable) -> Result<()> {
+        let serialized = executable.try_executable()?.try_serialize()?;
+        // write aside and rename, readers never see a partial entry
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let tmp = self.dir.join(format!(
+            ".{}.{}.{}.{}",
+            key,
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            TMP_EXTENSION
+        ));
+        let written = File::create(&tmp)
+            .and_then(|mut file| {
+                file.write_all(serialized.bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp, self.path(key)));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&tmp);
+            return Err(err.into());
+        }
+        self.evict()
+    }
+
+    /// Removes the least recently used entries until the cache fits in
+    /// `max_size`, and temporary files left by interrupted writes.
+    pub fn evict(&self) -> Result<()> {
+        let now = SystemTime::now();
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            // entries may be removed concurrently by other processes
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            match path.extension() {
+                Some(e) if e == EXTENSION => entries.push((modified, metadata.len(), path)),
+                // recent ones may still be written
+                Some(e)
+                    if e == TMP_EXTENSION
+                        && now.duration_since(modified).unwrap_or_default() > STALE_TMP_AGE =>
+                {
+                    let _ = fs::remove_file(&path);
+                }
+                _ => {}
+            }
+        }
+        let mut size = entries.iter().map(|e| e.1).sum::<u64>();
+        entries.sort_by_key(|e| e.0);
+        for (_, len, path) in entries {
+            if size <= self.max_size {
+                break;
+            }
+            let _ = fs::remove_file(&path);
+            size -= len;
+        }
+        Ok(())
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == EXTENSION) {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads `program` from the cache, or compiles and stores it.
+    pub fn compile(
+        &self,
+        client: &Client,
+        program: &Program,
+        options: &CompileOptions,
+    ) -> Result<LoadedExecutable> {
+        let key = self.key(client, program, options)?;
+        if let Some(executable) = self.get(client, &key) {
+            return Ok(executable);
+        }
+        let executable = client.compile(program, options.clone())?;
+        // failing to store only costs a compilation next time
+        let _ = self.put(&key, &executable);
+        Ok(executable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, ProgramFormat};
+
+    // a fresh cache in the temp dir, removed when dropped
+    struct TempCache(CompilationCache);
+
+    impl TempCache {
+        fn new(name: &str, max_size: u64) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "pjrt-compilation-cache-{}-{}",
+                name,
+                process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            Self(
+                CompilationCache::builder(dir)
+                    .max_size(max_size)
+                    .build()
+                    .unwrap(),
+            )
+        }
+
+        // writes `name` with `len` bytes, last modified `age` ago
+        fn write(&self, name: &str, len: usize, age: Duration) -> PathBuf {
+            let path = self.0.dir.join(name);
+            let file = File::create(&path).unwrap();
+            file.set_len(len as u64).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.dir);
+        }
+    }
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn key_is_deterministic() {
+        let program = Program::new(ProgramFormat::MLIR, "module {}");
+        let platform: &[&[u8]] = &[b"cpu", b"0.1"];
+        let options = CompileOptions::new()
+            .env_option_override("b", true)
+            .env_option_override("a", 1i64);
+        let key = cache_key(&program, &options, platform);
+        assert_eq!(key.len(), 64);
+        assert!(key.bytes().all(|b| b.is_ascii_hexdigit()));
+        // maps are encoded sorted, whatever the insertion order
+        let reordered = CompileOptions::new()
+            .env_option_override("a", 1i64)
+            .env_option_override("b", true);
+        assert_eq!(cache_key(&program, &reordered, platform), key);
+
+        let changed = CompileOptions::new()
+            .env_option_override("a", 2i64)
+            .env_option_override("b", true);
+        assert_ne!(cache_key(&program, &changed, platform), key);
+        let other = Program::new(ProgramFormat::MLIR, "module {} ");
+        assert_ne!(cache_key(&other, &options, platform), key);
+        // fields are length prefixed
+        assert_ne!(cache_key(&program, &options, &[b"cpu0", b".1"]), key);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = TempCache::new("lru", 10);
+        let old = cache.write("old.pjrt", 4, 3 * HOUR);
+        let mid = cache.write("mid.pjrt", 4, 2 * HOUR);
+        let new = cache.write("new.pjrt", 4, HOUR);
+        cache.0.evict().unwrap();
+        assert!(!old.exists());
+        assert!(mid.exists());
+        assert!(new.exists());
+
+        // loading an entry makes it the most recently used
+        assert_eq!(cache.0.load("mid", |bytes| Ok(bytes.len())), Some(4));
+        let newest = cache.write("newest.pjrt", 4, HOUR / 2);
+        cache.0.evict().unwrap();
+        assert!(mid.exists());
+        assert!(!new.exists());
+        assert!(newest.exists());
+    }
+
+    #[test]
+    fn removes_stale_temporary_files() {
+        let cache = TempCache::new("tmp", 1 << 20);
+        let stale = cache.write(".stale.1.0.tmp", 4, 2 * HOUR);
+        let fresh = cache.write(".fresh.1.1.tmp", 4, Duration::ZERO);
+        let other = cache.write("other.txt", 4, 2 * HOUR);
+        cache.0.evict().unwrap();
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn removes_broken_entries() {
+        let cache = TempCache::new("broken", 1 << 20);
+        assert_eq!(cache.0.load("missing", |_| Ok(())), None);
+        let broken = cache.write("broken.pjrt", 4, Duration::ZERO);
+        assert_eq!(
+            cache.0.load("broken", |_| Err::<(), _>(Error::NullPointer)),
+            None
+        );
+        assert!(!broken.exists());
+    }
+}
@@ -1,9 +1,11 @@
+use pjrt_sys::protos::xla::option_override_proto::Value;
 use pjrt_sys::protos::xla::{
     CompilationEnvironmentsProto, CompileOptionsProto, ExecutableBuildOptionsProto,
+    OptionOverrideProto, ShapeProto,
 };
 use prost::Message;
 
-use crate::{Client, Executable, LoadedExecutable, Result, TopologyDescription};
+use crate::{xla_flags, Client, Executable, LoadedExecutable, Result, Shape, TopologyDescription};
 
 pub trait CompileToExecutable<T> {
     fn compile(
@@ -54,11 +56,103 @@ impl CompileOptions {
         self
     }
 
+    /// The layouts of the arguments that the computation should expect.
+    pub fn argument_layouts(mut self, argument_layouts: &[Shape]) -> Result<Self> {
+        self.proto.argument_layouts = argument_layouts
+            .iter()
+            .map(ShapeProto::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self)
+    }
+
+    /// Compiles the program with its parameters packed in a single tuple.
+    /// Executions still take the flat list of tuple elements.
+    pub fn parameter_is_tupled_arguments(mut self, parameter_is_tupled_arguments: bool) -> Self {
+        self.proto.parameter_is_tupled_arguments = parameter_is_tupled_arguments;
+        self
+    }
+
+    /// If true, the executable can be run on any device. May only be true if
+    /// no device assignment is set.
+    pub fn compile_portable_executable(mut self, compile_portable_executable: bool) -> Self {
+        self.proto.compile_portable_executable = compile_portable_executable;
+        self
+    }
+
+    /// XLA compilation profile version.
+    pub fn profile_version(mut self, profile_version: i64) -> Self {
+        self.proto.profile_version = profile_version;
+        self
+    }
+
+    /// Overrides the environment option `name`, e.g. a flag of the plugin.
+    pub fn env_option_override(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<OptionOverride>,
+    ) -> Self {
+        self.proto
+            .env_option_overrides
+            .insert(name.into(), value.into().into());
+        self
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         self.proto.encode_to_vec()
     }
 }
 
+/// The value of an environment option override.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionOverride {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+}
+
+impl From<String> for OptionOverride {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for OptionOverride {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<bool> for OptionOverride {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for OptionOverride {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for OptionOverride {
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+impl From<OptionOverride> for OptionOverrideProto {
+    fn from(value: OptionOverride) -> Self {
+        let value = match value {
+            OptionOverride::String(v) => Value::StringField(v),
+            OptionOverride::Bool(v) => Value::BoolField(v),
+            OptionOverride::Int(v) => Value::IntField(v),
+            OptionOverride::Double(v) => Value::DoubleField(v),
+        };
+        OptionOverrideProto { value: Some(value) }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExecutableBuildOptions {
     proto: ExecutableBuildOptionsProto,
@@ -215,6 +309,9 @@ impl ExecutableBuildOptions {
 
     /// Expose access to the XLA debug options which will be passed to the
     /// compilation process.
+    ///
+    /// Debug options replace XLA's flag defaults as a whole, see
+    /// [`DebugOptions`].
     pub fn debug_options(mut self, debug_options: impl Into<Option<DebugOptions>>) -> Self {
         self.proto.debug_options = debug_options.into().map(|v| v.proto);
         self
@@ -238,12 +335,24 @@ impl Default for ExecutableBuildOptions {
     }
 }
 
+/// XLA debug options of a compilation.
+///
+/// # Defaults
+///
+/// These are not `XLA_FLAGS` semantics. Once set in the
+/// [`ExecutableBuildOptions`], the options are used as they are instead of
+/// XLA's defaults, and every field left unset is zero. Options that XLA turns
+/// on by default, e.g. `xla_gpu_autotune_level` or the cpu thunk runtime, are
+/// then off unless set here too. Leave the debug options unset to compile with
+/// XLA's defaults.
 #[derive(Debug, Clone)]
 pub struct DebugOptions {
     proto: pjrt_sys::protos::xla::DebugOptions,
 }
 
 impl DebugOptions {
+    /// Options with every field zero, not XLA's defaults, see
+    /// [`DebugOptions#defaults`].
     pub fn new() -> Self {
         Self {
             proto: pjrt_sys::protos::xla::DebugOptions::default(),
@@ -258,6 +367,130 @@ impl DebugOptions {
         &mut self.proto
     }
 
+    /// Parses flags in the `XLA_FLAGS` syntax, e.g.
+    /// `--xla_dump_to=/tmp/dump --xla_cpu_enable_fast_math=true`, on top of
+    /// [`DebugOptions::new`]. Unknown flags are errors.
+    ///
+    /// Only the syntax is shared with `XLA_FLAGS`: fields without a flag are
+    /// zero, not XLA's defaults, see [`DebugOptions#defaults`]. Passing only
+    /// `--xla_dump_to=/tmp` turns off every option XLA enables by default.
+    pub fn from_xla_flags(flags: &str) -> Result<Self> {
+        Self::new().xla_flags(flags)
+    }
+
+    /// Applies flags in the `XLA_FLAGS` syntax on top of these options, see
+    /// [`Self::from_xla_flags`].
+    pub fn xla_flags(mut self, flags: &str) -> Result<Self> {
+        xla_flags::merge_xla_flags(&mut self.proto, flags)?;
+        Ok(self)
+    }
+
+    /// Directory to dump into.
+    pub fn dump_to(mut self, dump_to: impl Into<String>) -> Self {
+        self.proto.xla_dump_to = dump_to.into();
+        self
+    }
+
+    /// If specified, will only dump modules which match this regexp.
+    pub fn dump_hlo_module_re(mut self, dump_hlo_module_re: impl Into<String>) -> Self {
+        self.proto.xla_dump_hlo_module_re = dump_hlo_module_re.into();
+        self
+    }
+
+    /// If this flag is specified, will also dump HLO before and after passes that
+    /// match this regular expression. Set to .* to dump before/after all passes.
+    pub fn dump_hlo_pass_re(mut self, dump_hlo_pass_re: impl Into<String>) -> Self {
+        self.proto.xla_dump_hlo_pass_re = dump_hlo_pass_re.into();
+        self
+    }
+
+    /// Dump HLO as text.
+    pub fn dump_hlo_as_text(mut self, dump_hlo_as_text: bool) -> Self {
+        self.proto.xla_dump_hlo_as_text = dump_hlo_as_text;
+        self
+    }
+
+    /// Dump HLO as a binary `HloProto`.
+    pub fn dump_hlo_as_proto(mut self, dump_hlo_as_proto: bool) -> Self {
+        self.proto.xla_dump_hlo_as_proto = dump_hlo_as_proto;
+        self
+    }
+
+    /// Dump HLO graphs in the DOT format.
+    pub fn dump_hlo_as_dot(mut self, dump_hlo_as_dot: bool) -> Self {
+        self.proto.xla_dump_hlo_as_dot = dump_hlo_as_dot;
+        self
+    }
+
+    /// Dump HLO graphs as an HTML (DOT -> SVG inlined in HTML)
+    pub fn dump_hlo_as_html(mut self, dump_hlo_as_html: bool) -> Self {
+        self.proto.xla_dump_hlo_as_html = dump_hlo_as_html;
+        self
+    }
+
+    /// List of HLO passes to disable. These names must exactly match the pass
+    /// names as specified by the HloPassInterface::name() method.
+    pub fn disable_hlo_passes<S: Into<String>>(
+        mut self,
+        disable_hlo_passes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.proto.xla_disable_hlo_passes =
+            disable_hlo_passes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Disables all HLO passes.
+    pub fn disable_all_hlo_passes(mut self, disable_all_hlo_passes: bool) -> Self {
+        self.proto.xla_disable_all_hlo_passes = disable_all_hlo_passes;
+        self
+    }
+
+    /// When true, "unsafe" mathematical optimizations are enabled on the cpu.
+    pub fn cpu_enable_fast_math(mut self, cpu_enable_fast_math: bool) -> Self {
+        self.proto.xla_cpu_enable_fast_math = cpu_enable_fast_math;
+        self
+    }
+
+    /// When false we always propagate NaNs through Min and Max on the cpu.
+    pub fn cpu_enable_fast_min_max(mut self, cpu_enable_fast_min_max: bool) -> Self {
+        self.proto.xla_cpu_enable_fast_min_max = cpu_enable_fast_min_max;
+        self
+    }
+
+    /// When fast math is enabled, whether we allow operations to produce NaNs.
+    pub fn cpu_fast_math_honor_nans(mut self, cpu_fast_math_honor_nans: bool) -> Self {
+        self.proto.xla_cpu_fast_math_honor_nans = cpu_fast_math_honor_nans;
+        self
+    }
+
+    /// When fast math is enabled, whether we allow operations to produce
+    /// infinites.
+    pub fn cpu_fast_math_honor_infs(mut self, cpu_fast_math_honor_infs: bool) -> Self {
+        self.proto.xla_cpu_fast_math_honor_infs = cpu_fast_math_honor_infs;
+        self
+    }
+
+    /// When fast math is enabled, whether we forbid to use the reciprocal of an
+    /// argument instead of division.
+    pub fn cpu_fast_math_honor_division(mut self, cpu_fast_math_honor_division: bool) -> Self {
+        self.proto.xla_cpu_fast_math_honor_division = cpu_fast_math_honor_division;
+        self
+    }
+
+    /// When fast math is enabled, whether we forbid to approximate calculations
+    /// for functions.
+    pub fn cpu_fast_math_honor_functions(mut self, cpu_fast_math_honor_functions: bool) -> Self {
+        self.proto.xla_cpu_fast_math_honor_functions = cpu_fast_math_honor_functions;
+        self
+    }
+
+    /// Autotuning level of the gpu backend, 0 disables autotuning. XLA
+    /// defaults to 4, an empty `DebugOptions` has 0.
+    pub fn gpu_autotune_level(mut self, gpu_autotune_level: i32) -> Self {
+        self.proto.xla_gpu_autotune_level = gpu_autotune_level;
+        self
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         self.proto.encode_to_vec()
     }
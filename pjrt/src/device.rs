@@ -29,6 +29,10 @@ pub struct Device {
     pub(crate) ptr: *mut PJRT_Device,
 }
 
+// devices are owned by the client and never change after it is created.
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
+
 impl Device {
     pub(crate) fn wrap(client: &Client, ptr: *mut PJRT_Device) -> Device {
         assert!(!ptr.is_null());
@@ -43,63 +47,70 @@ impl Device {
     }
 
     pub fn description(&self) -> DeviceDescription {
+        self.try_description().expect("PJRT_Device_GetDescription")
+    }
+
+    pub fn try_description(&self) -> Result<DeviceDescription> {
         let mut args = PJRT_Device_GetDescription_Args::new();
         args.device = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Device_GetDescription(args)
-            .expect("PJRT_Device_GetDescription");
-        DeviceDescription::wrap(self.client.api(), args.device_description)
+        args = self.client.api().PJRT_Device_GetDescription(args)?;
+        Ok(DeviceDescription::wrap(
+            self.client.api(),
+            args.device_description,
+        ))
     }
 
     pub fn is_addressable(&self) -> bool {
+        self.try_is_addressable()
+            .expect("PJRT_Device_IsAddressable")
+    }
+
+    pub fn try_is_addressable(&self) -> Result<bool> {
         let mut args = PJRT_Device_IsAddressable_Args::new();
         args.device = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Device_IsAddressable(args)
-            .expect("PJRT_Device_IsAddressable");
-        args.is_addressable
+        args = self.client.api().PJRT_Device_IsAddressable(args)?;
+        Ok(args.is_addressable)
     }
 
     pub fn local_hardware_id(&self) -> LocalHardwareId {
+        self.try_local_hardware_id()
+            .expect("PJRT_Device_LocalHardwareId")
+    }
+
+    pub fn try_local_hardware_id(&self) -> Result<LocalHardwareId> {
         let mut args = PJRT_Device_LocalHardwareId_Args::new();
         args.device = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Device_LocalHardwareId(args)
-            .expect("PJRT_Device_LocalHardwareId");
-        args.local_hardware_id
+        args = self.client.api().PJRT_Device_LocalHardwareId(args)?;
+        Ok(args.local_hardware_id)
     }
 
     pub fn addressable_memories(&self) -> Vec<Memory> {
+        self.try_addressable_memories()
+            .expect("PJRT_Device_AddressableMemories")
+    }
+
+    pub fn try_addressable_memories(&self) -> Result<Vec<Memory>> {
         let mut args = PJRT_Device_AddressableMemories_Args::new();
         args.device = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Device_AddressableMemories(args)
-            .expect("PJRT_Device_AddressableMemories");
+        args = self.client.api().PJRT_Device_AddressableMemories(args)?;
         let memories = unsafe { slice::from_raw_parts(args.memories, args.num_memories) };
-        memories
+        Ok(memories
             .iter()
             .cloned()
             .map(|d| Memory::wrap(&self.client, d))
-            .collect()
+            .collect())
     }
 
     pub fn default_memory(&self) -> Memory {
+        self.try_default_memory()
+            .expect("PJRT_Device_DefaultMemory")
+    }
+
+    pub fn try_default_memory(&self) -> Result<Memory> {
         let mut args = PJRT_Device_DefaultMemory_Args::new();
         args.device = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Device_DefaultMemory(args)
-            .expect("PJRT_Device_DefaultMemory");
-        Memory::wrap(&self.client, args.memory)
+        args = self.client.api().PJRT_Device_DefaultMemory(args)?;
+        Ok(Memory::wrap(&self.client, args.memory))
     }
 
     pub fn memory_stats(&self) -> Result<MemoryStats> {
@@ -9,7 +9,7 @@ use pjrt_sys::{
 };
 
 use crate::named_value::NamedValueMap;
-use crate::{utils, Api, GlobalDeviceId};
+use crate::{utils, Api, GlobalDeviceId, Result};
 
 pub struct DeviceDescription {
     api: Api,
@@ -30,63 +30,79 @@ impl DeviceDescription {
     }
 
     pub fn id(&self) -> GlobalDeviceId {
+        self.try_id().expect("PJRT_DeviceDescription_Id")
+    }
+
+    pub fn try_id(&self) -> Result<GlobalDeviceId> {
         let mut args = PJRT_DeviceDescription_Id_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_Id(args)
-            .expect("PJRT_DeviceDescription_Id");
-        args.id
+        args = self.api.PJRT_DeviceDescription_Id(args)?;
+        Ok(args.id)
     }
 
     pub fn process_index(&self) -> i32 {
+        self.try_process_index()
+            .expect("PJRT_DeviceDescription_ProcessIndex")
+    }
+
+    pub fn try_process_index(&self) -> Result<i32> {
         let mut args = PJRT_DeviceDescription_ProcessIndex_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_ProcessIndex(args)
-            .expect("PJRT_DeviceDescription_ProcessIndex");
-        args.process_index
+        args = self.api.PJRT_DeviceDescription_ProcessIndex(args)?;
+        Ok(args.process_index)
     }
 
     pub fn attributes(&self) -> NamedValueMap {
+        self.try_attributes()
+            .expect("PJRT_DeviceDescription_Attributes")
+    }
+
+    pub fn try_attributes(&self) -> Result<NamedValueMap> {
         let mut args = PJRT_DeviceDescription_Attributes_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_Attributes(args)
-            .expect("PJRT_DeviceDescription_Attributes");
-        utils::to_named_value_map(args.attributes, args.num_attributes)
+        args = self.api.PJRT_DeviceDescription_Attributes(args)?;
+        Ok(utils::to_named_value_map(
+            args.attributes,
+            args.num_attributes,
+        ))
     }
 
     pub fn kind(&self) -> Cow<'_, str> {
+        self.try_kind().expect("PJRT_DeviceDescription_Kind")
+    }
+
+    pub fn try_kind(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_DeviceDescription_Kind_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_Kind(args)
-            .expect("PJRT_DeviceDescription_Kind");
-        utils::str_from_raw(args.device_kind, args.device_kind_size)
+        args = self.api.PJRT_DeviceDescription_Kind(args)?;
+        Ok(utils::str_from_raw(args.device_kind, args.device_kind_size))
     }
 
     pub fn debug_string(&self) -> Cow<'_, str> {
+        self.try_debug_string()
+            .expect("PJRT_DeviceDescription_DebugString")
+    }
+
+    pub fn try_debug_string(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_DeviceDescription_DebugString_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_DebugString(args)
-            .expect("PJRT_DeviceDescription_DebugString");
-        utils::str_from_raw(args.debug_string, args.debug_string_size)
+        args = self.api.PJRT_DeviceDescription_DebugString(args)?;
+        Ok(utils::str_from_raw(
+            args.debug_string,
+            args.debug_string_size,
+        ))
     }
 
     pub fn to_string(&self) -> Cow<'_, str> {
+        self.try_to_string()
+            .expect("PJRT_DeviceDescription_ToString")
+    }
+
+    pub fn try_to_string(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_DeviceDescription_ToString_Args::new();
         args.device_description = self.ptr;
-        args = self
-            .api
-            .PJRT_DeviceDescription_ToString(args)
-            .expect("PJRT_DeviceDescription_ToString");
-        utils::str_from_raw(args.to_string, args.to_string_size)
+        args = self.api.PJRT_DeviceDescription_ToString(args)?;
+        Ok(utils::str_from_raw(args.to_string, args.to_string_size))
     }
 }
 
@@ -22,7 +22,6 @@ impl Drop for CopyToDeviceStream {
 }
 
 impl CopyToDeviceStream {
-    #[allow(dead_code)]
     pub(crate) fn wrap(api: &Api, ptr: *mut PJRT_CopyToDeviceStream) -> Self {
         assert!(!ptr.is_null());
         Self {
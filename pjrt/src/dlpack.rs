@@ -0,0 +1,265 @@
+use std::ffi::c_void;
+use std::{ptr, slice};
+
+use pjrt_sys::{
+    DLDataType, DLDataTypeCode_kDLBfloat, DLDataTypeCode_kDLBool, DLDataTypeCode_kDLComplex,
+    DLDataTypeCode_kDLFloat, DLDataTypeCode_kDLInt, DLDataTypeCode_kDLUInt, DLDevice, DLDeviceType,
+    DLDeviceType_kDLCPU, DLDeviceType_kDLCUDA, DLDeviceType_kDLROCM, DLManagedTensor, DLTensor,
+    PJRT_Buffer_DecreaseExternalReferenceCount_Args,
+    PJRT_Buffer_IncreaseExternalReferenceCount_Args,
+    PJRT_Buffer_OpaqueDeviceMemoryDataPointer_Args,
+};
+
+use crate::{Buffer, Client, Device, Error, MemoryLayout, OnDeleteCallback, PrimitiveType, Result};
+
+impl TryFrom<PrimitiveType> for DLDataType {
+    type Error = Error;
+
+    fn try_from(ty: PrimitiveType) -> Result<Self> {
+        let (code, bits) = match ty {
+            PrimitiveType::Pred => (DLDataTypeCode_kDLBool, 8),
+            PrimitiveType::S8 => (DLDataTypeCode_kDLInt, 8),
+            PrimitiveType::S16 => (DLDataTypeCode_kDLInt, 16),
+            PrimitiveType::S32 => (DLDataTypeCode_kDLInt, 32),
+            PrimitiveType::S64 => (DLDataTypeCode_kDLInt, 64),
+            PrimitiveType::U8 => (DLDataTypeCode_kDLUInt, 8),
+            PrimitiveType::U16 => (DLDataTypeCode_kDLUInt, 16),
+            PrimitiveType::U32 => (DLDataTypeCode_kDLUInt, 32),
+            PrimitiveType::U64 => (DLDataTypeCode_kDLUInt, 64),
+            PrimitiveType::F16 => (DLDataTypeCode_kDLFloat, 16),
+            PrimitiveType::F32 => (DLDataTypeCode_kDLFloat, 32),
+            PrimitiveType::F64 => (DLDataTypeCode_kDLFloat, 64),
+            PrimitiveType::BF16 => (DLDataTypeCode_kDLBfloat, 16),
+            PrimitiveType::C64 => (DLDataTypeCode_kDLComplex, 64),
+            PrimitiveType::C128 => (DLDataTypeCode_kDLComplex, 128),
+            _ => return Err(Error::NotSupportedType(ty)),
+        };
+        Ok(DLDataType {
+            code: code as u8,
+            bits,
+            lanes: 1,
+        })
+    }
+}
+
+impl TryFrom<DLDataType> for PrimitiveType {
+    type Error = Error;
+
+    #[allow(non_upper_case_globals)]
+    fn try_from(dtype: DLDataType) -> Result<Self> {
+        if dtype.lanes != 1 {
+            return Err(Error::DlpackError(format!(
+                "vector types are not supported, got {} lanes",
+                dtype.lanes
+            )));
+        }
+        match (dtype.code as _, dtype.bits) {
+            (DLDataTypeCode_kDLBool, 8) => Ok(PrimitiveType::Pred),
+            (DLDataTypeCode_kDLInt, 8) => Ok(PrimitiveType::S8),
+            (DLDataTypeCode_kDLInt, 16) => Ok(PrimitiveType::S16),
+            (DLDataTypeCode_kDLInt, 32) => Ok(PrimitiveType::S32),
+            (DLDataTypeCode_kDLInt, 64) => Ok(PrimitiveType::S64),
+            (DLDataTypeCode_kDLUInt, 8) => Ok(PrimitiveType::U8),
+            (DLDataTypeCode_kDLUInt, 16) => Ok(PrimitiveType::U16),
+            (DLDataTypeCode_kDLUInt, 32) => Ok(PrimitiveType::U32),
+            (DLDataTypeCode_kDLUInt, 64) => Ok(PrimitiveType::U64),
+            (DLDataTypeCode_kDLFloat, 16) => Ok(PrimitiveType::F16),
+            (DLDataTypeCode_kDLFloat, 32) => Ok(PrimitiveType::F32),
+            (DLDataTypeCode_kDLFloat, 64) => Ok(PrimitiveType::F64),
+            (DLDataTypeCode_kDLBfloat, 16) => Ok(PrimitiveType::BF16),
+            (DLDataTypeCode_kDLComplex, 64) => Ok(PrimitiveType::C64),
+            (DLDataTypeCode_kDLComplex, 128) => Ok(PrimitiveType::C128),
+            (code, bits) => Err(Error::DlpackError(format!(
+                "unsupported dtype code {} with {} bits",
+                code, bits
+            ))),
+        }
+    }
+}
+
+// the DLPack device type of the client's devices
+fn device_type(client: &Client) -> Result<DLDeviceType> {
+    match client.try_platform_name()?.as_ref() {
+        "cpu" => Ok(DLDeviceType_kDLCPU),
+        "cuda" => Ok(DLDeviceType_kDLCUDA),
+        "rocm" => Ok(DLDeviceType_kDLROCM),
+        platform => Err(Error::DlpackError(format!(
+            "platform {} has no DLPack device type",
+            platform
+        ))),
+    }
+}
+
+// owns the exported buffer and the arrays the tensor points to
+struct ManagerContext {
+    buffer: Buffer,
+    #[allow(dead_code)]
+    shape: Vec<i64>,
+    #[allow(dead_code)]
+    strides: Vec<i64>,
+    tensor: DLManagedTensor,
+}
+
+unsafe extern "C" fn dlpack_deleter(tensor: *mut DLManagedTensor) {
+    let ctx = unsafe { Box::from_raw((*tensor).manager_ctx as *mut ManagerContext) };
+    let mut args = PJRT_Buffer_DecreaseExternalReferenceCount_Args::new();
+    args.buffer = ctx.buffer.ptr;
+    let _ = ctx
+        .buffer
+        .client()
+        .api()
+        .PJRT_Buffer_DecreaseExternalReferenceCount(args);
+}
+
+impl Buffer {
+    /// Exports the buffer as a DLPack tensor once it is ready. The tensor holds
+    /// an external reference to the buffer until the consumer calls its deleter.
+    pub async fn to_dlpack(self) -> Result<*mut DLManagedTensor> {
+        // DLPack has no way to tell consumers the data isn't written yet
+        self.ready_event()?.await?;
+        self.export_dlpack()
+    }
+
+    /// Blocking version of [`Buffer::to_dlpack`].
+    pub fn to_dlpack_sync(self) -> Result<*mut DLManagedTensor> {
+        self.ready_event()?.wait()?;
+        self.export_dlpack()
+    }
+
+    fn export_dlpack(self) -> Result<*mut DLManagedTensor> {
+        let api = self.client().api();
+        let ty = self.try_primitive_type()?;
+        let dtype = DLDataType::try_from(ty)?;
+        let shape = self.try_dims()?;
+        let strides = self
+            .try_layout()?
+            .element_strides(&shape, dtype.bits as usize / 8)?;
+        let device = DLDevice {
+            device_type: device_type(self.client())?,
+            device_id: if self.try_is_on_cpu()? {
+                0
+            } else {
+                self.try_device()?.try_local_hardware_id()?
+            },
+        };
+        let mut args = PJRT_Buffer_IncreaseExternalReferenceCount_Args::new();
+        args.buffer = self.ptr;
+        api.PJRT_Buffer_IncreaseExternalReferenceCount(args)?;
+        let mut args = PJRT_Buffer_OpaqueDeviceMemoryDataPointer_Args::new();
+        args.buffer = self.ptr;
+        let data = match api.PJRT_Buffer_OpaqueDeviceMemoryDataPointer(args) {
+            Ok(args) => args.device_memory_ptr,
+            Err(err) => {
+                let mut args = PJRT_Buffer_DecreaseExternalReferenceCount_Args::new();
+                args.buffer = self.ptr;
+                // the first error is the one worth reporting
+                let _ = api.PJRT_Buffer_DecreaseExternalReferenceCount(args);
+                return Err(err);
+            }
+        };
+        let mut ctx = Box::new(ManagerContext {
+            buffer: self,
+            shape,
+            strides,
+            tensor: DLManagedTensor {
+                dl_tensor: DLTensor {
+                    data,
+                    device,
+                    ndim: 0,
+                    dtype,
+                    shape: ptr::null_mut(),
+                    strides: ptr::null_mut(),
+                    byte_offset: 0,
+                },
+                manager_ctx: ptr::null_mut(),
+                deleter: Some(dlpack_deleter),
+            },
+        });
+        ctx.tensor.dl_tensor.ndim = ctx.shape.len() as i32;
+        ctx.tensor.dl_tensor.shape = ctx.shape.as_mut_ptr();
+        ctx.tensor.dl_tensor.strides = ctx.strides.as_mut_ptr();
+        let ctx = Box::into_raw(ctx);
+        unsafe {
+            (*ctx).tensor.manager_ctx = ctx as *mut c_void;
+            Ok(&mut (*ctx).tensor as *mut DLManagedTensor)
+        }
+    }
+}
+
+// a DLPack tensor owned by a buffer view, deleted with it
+struct ManagedTensor(*mut DLManagedTensor);
+
+// the producer of the tensor must allow deleting it from any thread
+unsafe impl Send for ManagedTensor {}
+
+impl ManagedTensor {
+    fn delete(self) {
+        if let Some(deleter) = unsafe { (*self.0).deleter } {
+            unsafe { deleter(self.0) };
+        }
+    }
+}
+
+impl Client {
+    fn dlpack_device(&self, device: DLDevice) -> Result<Device> {
+        let device_type = device_type(self)?;
+        if device.device_type != device_type {
+            return Err(Error::DlpackError(format!(
+                "tensor on device type {} can't be used by platform {}",
+                device.device_type,
+                self.try_platform_name()?
+            )));
+        }
+        let mut devices = self.try_addressable_devices()?.into_iter();
+        if device_type == DLDeviceType_kDLCPU {
+            return devices.next().ok_or(Error::NoAddressableDevice);
+        }
+        for d in devices {
+            if d.try_local_hardware_id()? == device.device_id {
+                return Ok(d);
+            }
+        }
+        Err(Error::DlpackError(format!(
+            "no addressable device with hardware id {}",
+            device.device_id
+        )))
+    }
+
+    /// Wraps a DLPack tensor as a [`Buffer`] without copying it. The tensor's
+    /// deleter is called once the buffer is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `tensor` must point to a valid DLPack tensor, which the buffer takes
+    /// ownership of unless an error is returned.
+    pub unsafe fn from_dlpack(&self, tensor: *mut DLManagedTensor) -> Result<Buffer> {
+        let dl_tensor = unsafe { &(*tensor).dl_tensor };
+        let ty = PrimitiveType::try_from(dl_tensor.dtype)?;
+        let ndim = usize::try_from(dl_tensor.ndim)
+            .map_err(|_| Error::DlpackError(format!("negative ndim {}", dl_tensor.ndim)))?;
+        // shape and strides may be null for scalars
+        let dims = if ndim == 0 {
+            vec![]
+        } else {
+            unsafe { slice::from_raw_parts(dl_tensor.shape, ndim) }.to_vec()
+        };
+        let layout = if ndim == 0 || dl_tensor.strides.is_null() {
+            None
+        } else {
+            let strides = unsafe { slice::from_raw_parts(dl_tensor.strides, ndim) };
+            Some(MemoryLayout::Tiled(MemoryLayout::from_element_strides(
+                &dims, strides,
+            )?))
+        };
+        let device = self.dlpack_device(dl_tensor.device)?;
+        let data = unsafe { (dl_tensor.data as *mut u8).add(dl_tensor.byte_offset as usize) };
+        let tensor = ManagedTensor(tensor);
+        let on_delete: OnDeleteCallback = Box::new(move |_| tensor.delete());
+        unsafe {
+            self.create_view_of_device_buffer(data as *mut c_void, ty, dims, &device)
+                .maybe_layout(layout)
+                .on_delete(on_delete)
+                .build()
+        }
+    }
+}
@@ -28,6 +28,9 @@ pub enum Error {
     #[error("no addressable device")]
     NoAddressableDevice,
 
+    #[error("memory belongs to another client")]
+    MemoryNotOnClient,
+
     #[error("invalid primitive type: {0}")]
     InvalidPrimitiveType(i32),
 
@@ -37,9 +40,15 @@ pub enum Error {
     #[error("invalid memory layout type: {0}")]
     InvalidMemoryLayoutType(i32),
 
+    #[error("invalid memory layout: {0}")]
+    InvalidMemoryLayout(String),
+
     #[error("device not in device assignment: {0}")]
     DeviceNotInDeviceAssignment(GlobalDeviceId),
 
+    #[error("device not addressable by the executable: {0}")]
+    DeviceNotAddressable(GlobalDeviceId),
+
     #[error("invalid program format: {0}")]
     InvalidProgramFormat(String),
 
@@ -49,6 +58,12 @@ pub enum Error {
     #[error("null pointer")]
     NullPointer,
 
+    #[error("optimized program has no program shape")]
+    MissingProgramShape,
+
+    #[error("parameter index {index} out of range, executable has {count} arguments")]
+    ParameterIndexOutOfRange { index: usize, count: usize },
+
     #[error("plugin not found: {0}")]
     PluginNotFound(String),
 
@@ -58,9 +73,63 @@ pub enum Error {
     #[error("lib loading error: {0}")]
     LibLoadingError(#[from] libloading::Error),
 
+    #[error("extension not supported by plugin: {0}")]
+    ExtensionNotFound(&'static str),
+
+    #[error("protobuf decode error: {0}")]
+    ProtoDecodeError(#[from] prost::DecodeError),
+
     #[error("lock poison error: {0}")]
     PoisonError(String),
 
+    #[error("single device execution expects one argument list, got {0}")]
+    InvalidArgumentListCount(usize),
+
+    #[error("invalid host callbacks: {0}")]
+    InvalidHostCallbacks(String),
+
+    #[error("callback panicked")]
+    CallbackPanic,
+
+    #[error("ffi error: {0}")]
+    FfiError(String),
+
+    #[error("unsupported ffi {0} type: {1}")]
+    UnsupportedFfiType(&'static str, i32),
+
+    #[error("element type mismatch: expected {expected:?}, got {actual:?}")]
+    ElemTypeMismatch {
+        expected: PrimitiveType,
+        actual: PrimitiveType,
+    },
+
+    #[error("rank mismatch: expected {expected}, got {actual}")]
+    RankMismatch { expected: usize, actual: usize },
+
+    #[error("dims {dims:?} do not match {len} elements")]
+    ElementCountMismatch { dims: Vec<i64>, len: usize },
+
+    #[error("shape has {expected} leaves, got {actual} values")]
+    LeafCountMismatch { expected: usize, actual: usize },
+
+    #[error("unknown xla flag: {0}")]
+    UnknownXlaFlag(String),
+
+    #[error("invalid xla flag: {0}")]
+    InvalidXlaFlag(String),
+
+    #[error("invalid literal: {0}")]
+    InvalidLiteral(String),
+
+    #[error("dlpack error: {0}")]
+    DlpackError(String),
+
+    #[error("executable bundle mismatch: {0}")]
+    ExecutableBundleMismatch(String),
+
+    #[error("invalid executable bundle: {0}")]
+    InvalidExecutableBundle(String),
+
     #[error("unimplemented")]
     Unimplemeted,
 }
@@ -72,6 +141,28 @@ impl Error {
             _ => ErrorCode::Internal,
         }
     }
+
+    // a copy of an error kept to be reported again, events only fail with
+    // errors of PJRT calls
+    pub(crate) fn duplicate(&self) -> Error {
+        match self {
+            Error::PjrtError {
+                msg,
+                code,
+                backtrace,
+            } => Error::PjrtError {
+                msg: msg.clone(),
+                code: *code,
+                backtrace: backtrace.clone(),
+            },
+            Error::NullFunctionPointer(name) => Error::NullFunctionPointer(name),
+            err => Error::PjrtError {
+                msg: err.to_string(),
+                code: err.code(),
+                backtrace: String::new(),
+            },
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
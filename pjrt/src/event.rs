@@ -1,33 +1,43 @@
 use std::ffi::c_void;
 use std::future::Future;
-use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
+use atomic_waker::AtomicWaker;
 use pjrt_sys::{
-    PJRT_Error, PJRT_Error_Destroy_Args, PJRT_Event, PJRT_Event_Await_Args,
-    PJRT_Event_Destroy_Args, PJRT_Event_Error_Args, PJRT_Event_IsReady_Args,
-    PJRT_Event_OnReady_Args,
+    PJRT_Error, PJRT_Event, PJRT_Event_Await_Args, PJRT_Event_Destroy_Args, PJRT_Event_Error_Args,
+    PJRT_Event_IsReady_Args, PJRT_Event_OnReady_Args,
 };
 
-use crate::{Api, Result};
+use crate::{Api, Error, Result};
 
-extern "C" fn on_ready_callback(err: *mut PJRT_Error, cb_data: *mut c_void) {
-    let (api, waker) = unsafe { *Box::from_raw(cb_data as *mut (Api, Waker)) };
-    let mut args = PJRT_Error_Destroy_Args::new();
-    args.error = err;
-    api.PJRT_Error_Destroy(&mut args)
-        .expect("PJRT_Error_Destroy");
-    waker.wake();
+extern "C" fn on_ready_callback<F>(err: *mut PJRT_Error, cb_data: *mut c_void)
+where
+    F: FnOnce(Result<()>),
+{
+    let (api, callback) = unsafe { *Box::from_raw(cb_data as *mut (Api, F)) };
+    let result = api.err_or(err, ());
+    // unwinding into the runtime would abort
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(result)));
 }
 
 pub struct Event {
     api: Api,
     ptr: *mut PJRT_Event,
-    registered_callback: AtomicBool,
+    // completed by the event's on ready callback, registered by the first
+    // poll or timed wait
+    completion: OnceLock<Arc<Completion>>,
 }
 
+// PJRT events are thread safe futures: they may be queried, awaited and
+// given callbacks from any thread, callbacks run on a runtime thread.
+unsafe impl Send for Event {}
+unsafe impl Sync for Event {}
+
 impl Drop for Event {
     fn drop(&mut self) {
         let mut args = PJRT_Event_Destroy_Args::new();
@@ -44,7 +54,7 @@ impl Event {
         Self {
             api: api.clone(),
             ptr,
-            registered_callback: AtomicBool::new(false),
+            completion: OnceLock::new(),
         }
     }
 
@@ -52,7 +62,7 @@ impl Event {
         &self.api
     }
 
-    fn is_ready(&self) -> Result<bool> {
+    pub fn is_ready(&self) -> Result<bool> {
         let mut args = PJRT_Event_IsReady_Args::new();
         args.event = self.ptr;
         let args = self.api.PJRT_Event_IsReady(args)?;
@@ -65,48 +75,207 @@ impl Event {
         self.api.PJRT_Event_Error(args).map(|_| ())
     }
 
-    fn register_on_ready_callback(&self, waker: &Waker) -> Result<()> {
-        let mut cb_data = Box::new((self.api.clone(), waker.clone()));
+    /// Calls `callback` with the event's status once it is ready, on a runtime
+    /// thread, or right away if it already is.
+    pub fn on_ready<F>(&self, callback: F) -> Result<()>
+    where
+        F: FnOnce(Result<()>) + Send + 'static,
+    {
+        let cb_data = Box::into_raw(Box::new((self.api.clone(), callback)));
         let mut args = PJRT_Event_OnReady_Args::new();
         args.event = self.ptr;
-        args.user_arg = cb_data.as_mut() as *mut _ as *mut c_void;
-        args.callback = Some(on_ready_callback);
-        let args = self.api.PJRT_Event_OnReady(args);
-        mem::forget(cb_data);
-        args.map(|_| self.registered_callback.store(true, Ordering::SeqCst))
+        args.user_arg = cb_data as *mut c_void;
+        args.callback = Some(on_ready_callback::<F>);
+        match self.api.PJRT_Event_OnReady(args) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                drop(unsafe { Box::from_raw(cb_data) });
+                Err(err)
+            }
+        }
+    }
+
+    fn completion(&self) -> &Completion {
+        // the callback is registered once, concurrent callers wait for it
+        self.completion.get_or_init(|| {
+            let completion = Completion::new(1);
+            let on_ready = completion.clone();
+            if let Err(err) = self.on_ready(move |result| on_ready.complete(result)) {
+                completion.complete(Err(err));
+            }
+            completion
+        })
+    }
+
+    /// Keeps `value` alive until the event is ready.
+    pub(crate) fn keep_alive<T: Send + 'static>(&self, value: T) -> Result<()> {
+        self.on_ready(move |_| drop(value))
+    }
+
+    /// Waits for all `events`, failing with the first error.
+    pub fn join_all(events: Vec<Event>) -> JoinAll {
+        JoinAll::new(events)
     }
 
     #[must_use = "handle wait result"]
     pub fn wait(self) -> Result<()> {
         if self.is_ready()? {
-            return Ok(());
+            return self.error();
         }
         let mut args = PJRT_Event_Await_Args::new();
         args.event = self.ptr;
         let _ = self.api.PJRT_Event_Await(args)?;
         Ok(())
     }
+
+    /// Waits at most `timeout` for the event, returns whether it is ready.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool> {
+        if self.is_ready()? {
+            return self.error().map(|_| true);
+        }
+        match self.completion().wait(Some(timeout)) {
+            Some(result) => result.map(|_| true),
+            None => Ok(false),
+        }
+    }
 }
 
 impl Future for Event {
     type Output = Result<()>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.is_ready() {
-            Ok(is_ready) => {
-                if is_ready {
-                    Poll::Ready(self.error())
-                } else {
-                    if self.registered_callback.load(Ordering::SeqCst) {
-                        return Poll::Pending;
-                    }
-                    match self.register_on_ready_callback(cx.waker()) {
-                        Ok(_) => Poll::Pending,
-                        Err(err) => Poll::Ready(Err(err)),
-                    }
+        if self.completion.get().is_none() {
+            match self.is_ready() {
+                Ok(true) => return Poll::Ready(self.error()),
+                Ok(false) => {}
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        self.completion().poll(cx.waker())
+    }
+}
+
+// status of one or several events, completed by their on ready callbacks
+struct Completion {
+    remaining: AtomicUsize,
+    // the first error, kept so that the result can be read again
+    error: OnceLock<Error>,
+    waker: AtomicWaker,
+    // blocking waits sleep on the condvar, the lock orders their check of
+    // `remaining` with its notification
+    lock: Mutex<()>,
+    ready: Condvar,
+}
+
+impl Completion {
+    fn new(remaining: usize) -> Arc<Self> {
+        Arc::new(Self {
+            remaining: AtomicUsize::new(remaining),
+            error: OnceLock::new(),
+            waker: AtomicWaker::new(),
+            lock: Mutex::new(()),
+            ready: Condvar::new(),
+        })
+    }
+
+    fn complete(&self, result: Result<()>) {
+        if let Err(err) = result {
+            let _ = self.error.set(err);
+        }
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            drop(self.lock.lock().unwrap_or_else(|err| err.into_inner()));
+            self.ready.notify_all();
+            self.waker.wake();
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.remaining.load(Ordering::Acquire) == 0
+    }
+
+    fn result(&self) -> Result<()> {
+        match self.error.get() {
+            Some(err) => Err(err.duplicate()),
+            None => Ok(()),
+        }
+    }
+
+    // `None` if the timeout elapsed first
+    fn wait(&self, timeout: Option<Duration>) -> Option<Result<()>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        while !self.is_ready() {
+            guard = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.checked_duration_since(Instant::now())?;
+                    self.ready
+                        .wait_timeout(guard, timeout)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
                 }
+                None => self
+                    .ready
+                    .wait(guard)
+                    .unwrap_or_else(|err| err.into_inner()),
+            };
+        }
+        Some(self.result())
+    }
+
+    fn poll(&self, waker: &Waker) -> Poll<Result<()>> {
+        if self.is_ready() {
+            return Poll::Ready(self.result());
+        }
+        self.waker.register(waker);
+        // completed before the waker was registered
+        if self.is_ready() {
+            return Poll::Ready(self.result());
+        }
+        Poll::Pending
+    }
+}
+
+/// Several events waited on as one, see [`Event::join_all`].
+pub struct JoinAll {
+    #[allow(dead_code)]
+    events: Vec<Event>,
+    completion: Arc<Completion>,
+}
+
+impl JoinAll {
+    fn new(events: Vec<Event>) -> Self {
+        let completion = Completion::new(events.len());
+        for event in events.iter() {
+            let on_ready = completion.clone();
+            if let Err(err) = event.on_ready(move |result| on_ready.complete(result)) {
+                completion.complete(Err(err));
             }
-            Err(err) => Poll::Ready(Err(err)),
         }
+        Self { events, completion }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.completion.is_ready()
+    }
+
+    #[must_use = "handle wait result"]
+    pub fn wait(self) -> Result<()> {
+        self.completion.wait(None).unwrap_or(Ok(()))
+    }
+
+    /// Waits at most `timeout` for all events, returns whether they are ready.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool> {
+        match self.completion.wait(Some(timeout)) {
+            Some(result) => result.map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
+
+impl Future for JoinAll {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.completion.poll(cx.waker())
     }
 }
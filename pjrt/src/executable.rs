@@ -1,6 +1,10 @@
 use std::borrow::{Borrow, Cow};
+use std::sync::OnceLock;
 
 use bon::bon;
+use pjrt_sys::protos::xla::{
+    self, HloModuleProto, HloModuleProtoWithConfig, ProgramShapeProto, ShapeProto,
+};
 use pjrt_sys::{
     PJRT_Executable, PJRT_Executable_Destroy_Args, PJRT_Executable_Fingerprint_Args,
     PJRT_Executable_GetCompiledMemoryStats_Args, PJRT_Executable_GetCostAnalysis_Args,
@@ -8,20 +12,35 @@ use pjrt_sys::{
     PJRT_Executable_NumReplicas_Args, PJRT_Executable_OptimizedProgram_Args,
     PJRT_Executable_OutputDimensions_Args, PJRT_Executable_OutputElementTypes_Args,
     PJRT_Executable_OutputMemoryKinds_Args, PJRT_Executable_Serialize_Args,
-    PJRT_Executable_SizeOfGeneratedCodeInBytes_Args, PJRT_SerializedExecutable,
+    PJRT_Executable_SizeOfGeneratedCodeInBytes_Args, PJRT_Program, PJRT_SerializedExecutable,
 };
+use prost::Message;
 
 use crate::program::ProgramFormat;
 use crate::{
-    utils, Api, Client, CompileOptions, CompileToExecutable, NamedValueMap, PrimitiveType, Program,
-    Result, TopologyDescription,
+    utils, Api, Client, CompileOptions, CompileToExecutable, Error, MemoryLayout, NamedValueMap,
+    PrimitiveType, Program, Result, Shape, TopologyDescription,
 };
 
 pub struct Executable {
     api: Api,
     pub(crate) ptr: *mut PJRT_Executable,
+    // decoding the optimized program is costly, host transfers may look the
+    // layouts up for every buffer
+    program_shape: OnceLock<ProgramShapeProto>,
+    layouts: OnceLock<CompiledLayouts>,
+}
+
+struct CompiledLayouts {
+    parameters: Vec<MemoryLayout>,
+    outputs: Vec<MemoryLayout>,
 }
 
+// executables are immutable once compiled, PJRT_Executable_* calls only
+// read them.
+unsafe impl Send for Executable {}
+unsafe impl Sync for Executable {}
+
 impl Drop for Executable {
     fn drop(&mut self) {
         let mut args = PJRT_Executable_Destroy_Args::new();
@@ -39,6 +58,8 @@ impl Executable {
         Self {
             api: api.clone(),
             ptr,
+            program_shape: OnceLock::new(),
+            layouts: OnceLock::new(),
         }
     }
 
@@ -61,76 +82,89 @@ impl Executable {
     }
 
     pub fn name(&self) -> Cow<'_, str> {
+        self.try_name().expect("PJRT_Executable_Name")
+    }
+
+    pub fn try_name(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Executable_Name_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_Name(args)
-            .expect("PJRT_Executable_Name");
-        utils::str_from_raw(args.executable_name, args.executable_name_size)
+        args = self.api.PJRT_Executable_Name(args)?;
+        Ok(utils::str_from_raw(
+            args.executable_name,
+            args.executable_name_size,
+        ))
     }
 
     pub fn num_replicas(&self) -> usize {
+        self.try_num_replicas()
+            .expect("PJRT_Executable_NumReplicas")
+    }
+
+    pub fn try_num_replicas(&self) -> Result<usize> {
         let mut args = PJRT_Executable_NumReplicas_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_NumReplicas(args)
-            .expect("PJRT_Executable_NumReplicas");
-        args.num_replicas
+        args = self.api.PJRT_Executable_NumReplicas(args)?;
+        Ok(args.num_replicas)
     }
 
     pub fn num_partitions(&self) -> usize {
+        self.try_num_partitions()
+            .expect("PJRT_Executable_NumPartitions")
+    }
+
+    pub fn try_num_partitions(&self) -> Result<usize> {
         let mut args = PJRT_Executable_NumPartitions_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_NumPartitions(args)
-            .expect("PJRT_Executable_NumPartitions");
-        args.num_partitions
+        args = self.api.PJRT_Executable_NumPartitions(args)?;
+        Ok(args.num_partitions)
     }
 
     pub fn num_outputs(&self) -> usize {
+        self.try_num_outputs().expect("PJRT_Executable_NumOutputs")
+    }
+
+    pub fn try_num_outputs(&self) -> Result<usize> {
         let mut args = PJRT_Executable_NumOutputs_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_NumOutputs(args)
-            .expect("PJRT_Executable_NumOutputs");
-        args.num_outputs
+        args = self.api.PJRT_Executable_NumOutputs(args)?;
+        Ok(args.num_outputs)
     }
 
     pub fn code_size(&self) -> i64 {
+        self.try_code_size()
+            .expect("PJRT_Executable_SizeOfGeneratedCodeInBytes")
+    }
+
+    pub fn try_code_size(&self) -> Result<i64> {
         let mut args = PJRT_Executable_SizeOfGeneratedCodeInBytes_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_SizeOfGeneratedCodeInBytes(args)
-            .expect("PJRT_Executable_SizeOfGeneratedCodeInBytes");
-        args.size_in_bytes
+        args = self.api.PJRT_Executable_SizeOfGeneratedCodeInBytes(args)?;
+        Ok(args.size_in_bytes)
     }
 
     pub fn output_primitive_types(&self) -> Vec<PrimitiveType> {
+        self.try_output_primitive_types()
+            .expect("PJRT_Executable_OutputElementTypes")
+    }
+
+    pub fn try_output_primitive_types(&self) -> Result<Vec<PrimitiveType>> {
         let mut args = PJRT_Executable_OutputElementTypes_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_OutputElementTypes(args)
-            .expect("PJRT_Executable_OutputElementTypes");
+        args = self.api.PJRT_Executable_OutputElementTypes(args)?;
         let s = unsafe { std::slice::from_raw_parts(args.output_types, args.num_output_types) };
-        s.iter()
-            .map(|s| PrimitiveType::try_from(*s).expect("PrimitiveType"))
-            .collect()
+        s.iter().map(|s| PrimitiveType::try_from(*s)).collect()
     }
 
-    #[allow(clippy::needless_range_loop)]
     pub fn output_dims(&self) -> Vec<Vec<i64>> {
+        self.try_output_dims()
+            .expect("PJRT_Executable_OutputDimensions")
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    pub fn try_output_dims(&self) -> Result<Vec<Vec<i64>>> {
         let mut args = PJRT_Executable_OutputDimensions_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_OutputDimensions(args)
-            .expect("PJRT_Executable_OutputDimensions");
+        args = self.api.PJRT_Executable_OutputDimensions(args)?;
         let output_dim_size =
             unsafe { std::slice::from_raw_parts(args.dim_sizes, args.num_outputs) };
         let mut out = Vec::with_capacity(args.num_outputs);
@@ -139,57 +173,176 @@ impl Executable {
             let dims = s.to_owned();
             out.push(dims);
         }
-        out
+        Ok(out)
     }
 
     pub fn fingerprint(&self) -> Cow<'_, str> {
+        self.try_fingerprint().expect("PJRT_Executable_Fingerprint")
+    }
+
+    pub fn try_fingerprint(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Executable_Fingerprint_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_Fingerprint(args)
-            .expect("PJRT_Executable_Fingerprint");
-        utils::str_from_raw(
+        args = self.api.PJRT_Executable_Fingerprint(args)?;
+        Ok(utils::str_from_raw(
             args.executable_fingerprint,
             args.executable_fingerprint_size,
-        )
+        ))
     }
 
     pub fn cost_analysis(&self) -> NamedValueMap {
+        self.try_cost_analysis()
+            .expect("PJRT_Executable_GetCostAnalysis")
+    }
+
+    pub fn try_cost_analysis(&self) -> Result<NamedValueMap> {
         let mut args = PJRT_Executable_GetCostAnalysis_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_GetCostAnalysis(args)
-            .expect("PJRT_Executable_GetCostAnalysis");
-        utils::to_named_value_map(args.properties, args.num_properties)
+        args = self.api.PJRT_Executable_GetCostAnalysis(args)?;
+        Ok(utils::to_named_value_map(
+            args.properties,
+            args.num_properties,
+        ))
     }
 
     pub fn optimize(&self) -> Result<Program> {
+        let mut program = PJRT_Program::new();
         let mut args = PJRT_Executable_OptimizedProgram_Args::new();
         args.executable = self.ptr;
+        args.program = &mut program as *mut _;
         // first call to get the size
         args = self.api.PJRT_Executable_OptimizedProgram(args)?;
         // prepare the code buffer
-        let mut prog = unsafe { *args.program };
-        let mut code: Vec<u8> = vec![0; prog.code_size];
-        prog.code = code.as_mut_ptr() as *mut _;
+        let mut code: Vec<u8> = vec![0; program.code_size];
+        program.code = code.as_mut_ptr() as *mut _;
         // second call to get the code
-        args = self.api.PJRT_Executable_OptimizedProgram(args)?;
-        let prog = unsafe { *args.program };
-        let format = utils::str_from_raw(prog.format, prog.format_size);
+        self.api.PJRT_Executable_OptimizedProgram(args)?;
+        let format = utils::str_from_raw(program.format, program.format_size);
         let format = ProgramFormat::try_from(format.borrow())?;
         Ok(Program::new(format, code))
     }
 
-    #[allow(clippy::needless_range_loop)]
+    /// The program after XLA optimizations, e.g. to inspect its computations
+    /// and instructions.
+    pub fn optimized_hlo_module(&self) -> Result<HloModuleProto> {
+        let program = self.optimize()?;
+        match program.format() {
+            ProgramFormat::HLO => Ok(HloModuleProto::decode(program.code())?),
+            // what the cpu and gpu plugins return
+            ProgramFormat::HLOWithConfig => HloModuleProtoWithConfig::decode(program.code())?
+                .hlo_module
+                .ok_or_else(|| {
+                    Error::InvalidProgramFormat("hlo_with_config without hlo_module".to_string())
+                }),
+            format => Err(Error::InvalidProgramFormat(format.as_str().to_string())),
+        }
+    }
+
+    fn host_program_shape(&self) -> Result<&ProgramShapeProto> {
+        if let Some(shape) = self.program_shape.get() {
+            return Ok(shape);
+        }
+        let module = self.optimized_hlo_module()?;
+        let shape = module
+            .host_program_shape
+            .or_else(|| {
+                module
+                    .computations
+                    .into_iter()
+                    .find(|c| c.id == module.entry_computation_id)
+                    .and_then(|c| c.program_shape)
+            })
+            .ok_or(Error::MissingProgramShape)?;
+        Ok(self.program_shape.get_or_init(|| shape))
+    }
+
+    fn compiled_layouts(&self) -> Result<&CompiledLayouts> {
+        if let Some(layouts) = self.layouts.get() {
+            return Ok(layouts);
+        }
+        let shape = self.host_program_shape()?;
+        let mut parameters = vec![];
+        for parameter in shape.parameters.iter() {
+            leaf_layouts(parameter, &mut parameters);
+        }
+        let mut outputs = vec![];
+        if let Some(result) = &shape.result {
+            leaf_layouts(result, &mut outputs);
+        }
+        Ok(self.layouts.get_or_init(|| CompiledLayouts {
+            parameters,
+            outputs,
+        }))
+    }
+
+    /// Shapes of the parameters, a single tuple when the program was compiled
+    /// with tupled arguments.
+    pub fn parameter_shapes(&self) -> Result<Vec<Shape>> {
+        let shape = self.host_program_shape()?;
+        shape.parameters.iter().map(Shape::try_from).collect()
+    }
+
+    /// Shape of the result, whose leaves are the flat outputs of an execution.
+    /// Read from the optimized program, which not every plugin provides.
+    pub fn output_shape(&self) -> Result<Shape> {
+        let shape = self.host_program_shape()?;
+        Shape::try_from(shape.result.as_ref().ok_or(Error::MissingProgramShape)?)
+    }
+
+    /// The flat outputs of an execution, as a tuple unless there is a single
+    /// one. Nested tuples of the result are not recovered.
+    pub fn flat_output_shape(&self) -> Result<Shape> {
+        let types = self.try_output_primitive_types()?;
+        let dims = self.try_output_dims()?;
+        let mut shapes = types
+            .into_iter()
+            .zip(dims)
+            .map(|(ty, dims)| match ty {
+                PrimitiveType::Token => Shape::Token,
+                ty => Shape::array(ty, dims),
+            })
+            .collect::<Vec<_>>();
+        if shapes.len() == 1 {
+            Ok(shapes.remove(0))
+        } else {
+            Ok(Shape::tuple(shapes))
+        }
+    }
+
+    /// Device layouts of the parameters the executable was compiled for,
+    /// with tuple parameters flattened.
+    pub fn parameter_layouts(&self) -> Result<Vec<MemoryLayout>> {
+        Ok(self.compiled_layouts()?.parameters.clone())
+    }
+
+    /// Device layout of the argument at `index` of an execution. Tuple
+    /// parameters are flattened, as executions take their elements.
+    pub fn parameter_layout(&self, index: usize) -> Result<MemoryLayout> {
+        let parameters = &self.compiled_layouts()?.parameters;
+        parameters
+            .get(index)
+            .cloned()
+            .ok_or(Error::ParameterIndexOutOfRange {
+                index,
+                count: parameters.len(),
+            })
+    }
+
+    /// Device layouts of the outputs, with a tuple result flattened.
+    pub fn output_layouts(&self) -> Result<Vec<MemoryLayout>> {
+        Ok(self.compiled_layouts()?.outputs.clone())
+    }
+
     pub fn output_memory_kinds(&self) -> Vec<Cow<'_, str>> {
+        self.try_output_memory_kinds()
+            .expect("PJRT_Executable_OutputMemoryKinds")
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    pub fn try_output_memory_kinds(&self) -> Result<Vec<Cow<'_, str>>> {
         let mut args = PJRT_Executable_OutputMemoryKinds_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_OutputMemoryKinds(args)
-            .expect("PJRT_Executable_OutputMemoryKinds");
+        args = self.api.PJRT_Executable_OutputMemoryKinds(args)?;
         let memory_kind_sizes =
             unsafe { std::slice::from_raw_parts(args.memory_kind_sizes, args.num_outputs) };
         let mut out = Vec::with_capacity(args.num_outputs);
@@ -198,37 +351,58 @@ impl Executable {
             let kind = utils::str_from_raw(ptr, memory_kind_sizes[i]);
             out.push(kind);
         }
-        out
+        Ok(out)
     }
 
     pub fn serialize(&self) -> SerializedExecutable {
+        self.try_serialize().expect("PJRT_Executable_Serialize")
+    }
+
+    pub fn try_serialize(&self) -> Result<SerializedExecutable> {
         let mut args = PJRT_Executable_Serialize_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_Serialize(args)
-            .expect("PJRT_Executable_Serialize");
-        SerializedExecutable {
+        args = self.api.PJRT_Executable_Serialize(args)?;
+        Ok(SerializedExecutable {
             ptr: args.serialized_executable,
             deleter: args
                 .serialized_executable_deleter
-                .expect("executable_deleter"),
+                .ok_or(Error::NullFunctionPointer("serialized_executable_deleter"))?,
             data_ptr: args.serialized_bytes as *const u8,
             data_len: args.serialized_bytes_size,
-        }
+        })
     }
 
     pub fn compiled_memory_stats(&self) -> CompiledMemoryStats {
+        self.try_compiled_memory_stats()
+            .expect("PJRT_Executable_GetCompiledMemoryStats")
+    }
+
+    pub fn try_compiled_memory_stats(&self) -> Result<CompiledMemoryStats> {
         let mut args = PJRT_Executable_GetCompiledMemoryStats_Args::new();
         args.executable = self.ptr;
-        args = self
-            .api
-            .PJRT_Executable_GetCompiledMemoryStats(args)
-            .expect("PJRT_Executable_GetCompiledMemoryStats");
-        CompiledMemoryStats::from(args)
+        args = self.api.PJRT_Executable_GetCompiledMemoryStats(args)?;
+        Ok(CompiledMemoryStats::from(args))
     }
 }
 
+fn leaf_layouts(shape: &ShapeProto, layouts: &mut Vec<MemoryLayout>) {
+    if shape.element_type == xla::PrimitiveType::Tuple as i32 {
+        for shape in shape.tuple_shapes.iter() {
+            leaf_layouts(shape, layouts);
+        }
+        return;
+    }
+    let layout = match &shape.layout {
+        Some(layout) => MemoryLayout::from(layout.as_ref()),
+        // no layout means the default, major to minor order
+        None => {
+            MemoryLayout::from_tiled((0..shape.dimensions.len() as i64).rev().collect::<Vec<_>>())
+                .build()
+        }
+    };
+    layouts.push(layout);
+}
+
 pub struct SerializedExecutable {
     ptr: *mut PJRT_SerializedExecutable,
     deleter: unsafe extern "C" fn(exec: *mut PJRT_SerializedExecutable),
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::Path;
+
+use pjrt_sys::protos::xla::{CompileOptionsProto, ExecutableAndOptionsProto, ShapeProto};
+use prost::Message;
+
+use crate::{Client, CompileOptions, Error, LoadedExecutable, Result, Shape};
+
+const MAGIC: &str = "pjrt-rs.ExecutableBundle";
+const VERSION: u32 = 1;
+
+// an `ExecutableAndOptionsProto` with fields XLA ignores, see
+// [`ExecutableBundle`]
+#[derive(Clone, PartialEq, Message)]
+struct ExecutableBundleProto {
+    #[prost(bytes = "vec", tag = "1")]
+    serialized_executable: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    compile_options: Option<CompileOptionsProto>,
+    #[prost(string, tag = "1000")]
+    magic: String,
+    #[prost(uint32, tag = "1001")]
+    version: u32,
+    #[prost(string, tag = "1002")]
+    platform_name: String,
+    #[prost(string, tag = "1003")]
+    platform_version: String,
+    #[prost(string, tag = "1004")]
+    fingerprint: String,
+    #[prost(message, optional, tag = "1005")]
+    output_shape: Option<ShapeProto>,
+}
+
+// the encoded magic field, which bundles start with
+fn magic_prefix() -> Vec<u8> {
+    ExecutableBundleProto {
+        magic: MAGIC.to_string(),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+/// A serialized executable together with the options, platform and output
+/// shape it was compiled with.
+///
+/// Unlike the bare bytes of [`Executable::serialize`](crate::Executable::serialize),
+/// a bundle refuses to load on a platform other than the one it was compiled for.
+///
+/// # Format
+///
+/// An encoded bundle is an `xla.ExecutableAndOptionsProto`, which XLA tooling
+/// reads as is, with extra fields it skips:
+///
+/// - 1000, first in the encoding: the magic string `pjrt-rs.ExecutableBundle`
+/// - 1001: the format version, currently 1
+/// - 1002, 1003: the platform name and version
+/// - 1004: the executable's fingerprint, empty if the plugin has none
+/// - 1005: the output shape, an `xla.ShapeProto`
+#[derive(Debug, Clone)]
+pub struct ExecutableBundle {
+    serialized_executable: Vec<u8>,
+    options: CompileOptions,
+    platform_name: String,
+    platform_version: String,
+    fingerprint: String,
+    output_shape: Shape,
+}
+
+impl ExecutableBundle {
+    /// Bundles `loaded`, which was compiled with `options`.
+    pub fn new(loaded: &LoadedExecutable, options: CompileOptions) -> Result<Self> {
+        let client = loaded.client();
+        let executable = loaded.try_executable()?;
+        Ok(Self {
+            serialized_executable: executable.try_serialize()?.bytes().to_vec(),
+            options,
+            platform_name: client.try_platform_name()?.into_owned(),
+            platform_version: client.try_platform_version()?.into_owned(),
+            // not every plugin computes fingerprints
+            fingerprint: executable
+                .try_fingerprint()
+                .map(|f| f.into_owned())
+                .unwrap_or_default(),
+            output_shape: loaded.output_shape()?.clone(),
+        })
+    }
+
+    pub fn serialized_executable(&self) -> &[u8] {
+        &self.serialized_executable
+    }
+
+    pub fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    pub fn platform_name(&self) -> &str {
+        &self.platform_name
+    }
+
+    pub fn platform_version(&self) -> &str {
+        &self.platform_version
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Shape of the result, see [`LoadedExecutable::output_shape`].
+    pub fn output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let proto = ExecutableBundleProto {
+            serialized_executable: self.serialized_executable.clone(),
+            compile_options: Some(self.options.proto().clone()),
+            magic: String::new(),
+            version: VERSION,
+            platform_name: self.platform_name.clone(),
+            platform_version: self.platform_version.clone(),
+            fingerprint: self.fingerprint.clone(),
+            output_shape: Some(ShapeProto::try_from(&self.output_shape)?),
+        };
+        // concatenated messages decode as one, the magic goes first
+        let mut bytes = magic_prefix();
+        bytes.extend(proto.encode_to_vec());
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if !bytes.starts_with(&magic_prefix()) {
+            return Err(Error::InvalidExecutableBundle(
+                "missing magic, not an executable bundle".to_string(),
+            ));
+        }
+        let proto = ExecutableBundleProto::decode(bytes)?;
+        if proto.version != VERSION {
+            return Err(Error::InvalidExecutableBundle(format!(
+                "unsupported version {}, expected {}",
+                proto.version, VERSION
+            )));
+        }
+        let mut options = CompileOptions::new();
+        if let Some(compile_options) = proto.compile_options {
+            *options.proto_mut() = compile_options;
+        }
+        let output_shape = proto
+            .output_shape
+            .as_ref()
+            .ok_or_else(|| Error::InvalidExecutableBundle("missing output shape".to_string()))?;
+        Ok(Self {
+            serialized_executable: proto.serialized_executable,
+            options,
+            platform_name: proto.platform_name,
+            platform_version: proto.platform_version,
+            fingerprint: proto.fingerprint,
+            output_shape: Shape::try_from(output_shape)?,
+        })
+    }
+
+    /// The executable and its options, as XLA serializes them.
+    pub fn to_executable_and_options(&self) -> ExecutableAndOptionsProto {
+        ExecutableAndOptionsProto {
+            serialized_executable: self.serialized_executable.clone(),
+            compile_options: Some(self.options.proto().clone()),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.encode()?)?;
+        Ok(())
+    }
+
+    /// Reads a bundle written by [`ExecutableBundle::save`].
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Loads the executable on `client`, which must be of the platform and
+    /// version the executable was compiled for.
+    pub fn load(&self, client: &Client) -> Result<LoadedExecutable> {
+        let platform_name = client.try_platform_name()?;
+        let platform_version = client.try_platform_version()?;
+        // plugins may crash deserializing executables of another platform
+        if platform_name != self.platform_name || platform_version != self.platform_version {
+            return Err(Error::ExecutableBundleMismatch(format!(
+                "compiled for platform {} {}, client is {} {}",
+                self.platform_name, self.platform_version, platform_name, platform_version
+            )));
+        }
+        let loaded = client.load_executable(&self.serialized_executable)?;
+        let executable = loaded.try_executable()?;
+        if !self.fingerprint.is_empty() {
+            if let Ok(fingerprint) = executable.try_fingerprint() {
+                if fingerprint != self.fingerprint {
+                    return Err(Error::ExecutableBundleMismatch(format!(
+                        "fingerprint {}, loaded executable has {}",
+                        self.fingerprint, fingerprint
+                    )));
+                }
+            }
+        }
+        let output_shape = loaded.output_shape()?;
+        if ShapeProto::try_from(output_shape)? != ShapeProto::try_from(&self.output_shape)? {
+            return Err(Error::ExecutableBundleMismatch(format!(
+                "output shape {:?}, loaded executable has {:?}",
+                self.output_shape, output_shape
+            )));
+        }
+        Ok(loaded)
+    }
+}
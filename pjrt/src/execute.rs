@@ -1,10 +1,149 @@
 use std::collections::HashSet;
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::{mem, ptr};
 
 use pjrt_sys::{
-    PJRT_Buffer, PJRT_ExecuteContext, PJRT_ExecuteContext_Destroy_Args, PJRT_ExecuteOptions,
+    PJRT_Buffer, PJRT_CallbackError, PJRT_Chunk, PJRT_CopyToDeviceStream, PJRT_Error,
+    PJRT_Error_Code, PJRT_ExecuteContext, PJRT_ExecuteContext_Destroy_Args, PJRT_ExecuteOptions,
+    PJRT_RecvCallbackInfo, PJRT_SendCallbackInfo,
 };
 
-use crate::{Api, Buffer, LoadedExecutable, Result};
+use crate::{
+    Api, BorrowedBuffer, Buffer, Chunk, CopyToDeviceStream, Device, Error, Event, LoadedExecutable,
+    Result, ShapeTree,
+};
+
+/// Called for each chunk sent to the host by a send op, with the total size of
+/// the transfer and whether this is the last chunk.
+pub type SendCallback = Box<dyn FnMut(Chunk, usize, bool) -> Result<()> + Send + 'static>;
+
+/// Called when a recv op needs data, with a stream to fill.
+pub type RecvCallback = Box<dyn FnMut(CopyToDeviceStream) + Send + 'static>;
+
+unsafe extern "C" fn send_callback(
+    chunk: *mut PJRT_Chunk,
+    callback_error: *mut PJRT_CallbackError,
+    total_size_in_bytes: usize,
+    done: bool,
+    user_arg: *mut c_void,
+) -> *mut PJRT_Error {
+    let callback = unsafe { &*(user_arg as *const Mutex<SendCallback>) };
+    let chunk = unsafe { Chunk::from_raw(chunk) };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut callback = callback
+            .lock()
+            .map_err(|err| Error::PoisonError(err.to_string()))?;
+        callback(chunk, total_size_in_bytes, done)
+    }))
+    .unwrap_or(Err(Error::CallbackPanic));
+    match result {
+        Ok(_) => ptr::null_mut(),
+        Err(err) => {
+            // without an error callback there is no way to report the error
+            let Some(err_callback) = (unsafe { callback_error.as_ref() }).and_then(|c| *c) else {
+                return ptr::null_mut();
+            };
+            let code = err.code() as PJRT_Error_Code;
+            let message = format!("{:?}", err);
+            let msg_bytes = message.as_bytes();
+            unsafe { (err_callback)(code, msg_bytes.as_ptr() as *const _, msg_bytes.len()) }
+        }
+    }
+}
+
+unsafe extern "C" fn recv_callback(stream: *mut PJRT_CopyToDeviceStream, user_arg: *mut c_void) {
+    let state = unsafe { &*(user_arg as *const RecvCallbackState) };
+    // the stream is owned by us from now on, dropping it destroys it
+    let stream = CopyToDeviceStream::wrap(&state.api, stream);
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| match state.callback.lock() {
+        Ok(mut callback) => callback(stream),
+        // the recv op waits for the whole transfer, complete it rather than
+        // hang the execution
+        Err(_) => {
+            let remaining = stream.total_bytes() - stream.current_bytes();
+            if remaining > 0 {
+                let _ = stream.call_add_chunk(Chunk::new(vec![0; remaining as usize]));
+            }
+        }
+    }));
+}
+
+struct RecvCallbackState {
+    api: Api,
+    callback: Arc<Mutex<RecvCallback>>,
+}
+
+// the callbacks PJRT calls into, until the execution is done
+type HostCallbacksState = (Vec<Arc<Mutex<SendCallback>>>, Vec<RecvCallbackState>);
+
+/// Send/recv callbacks of a single execution, laid out as PJRT expects them.
+pub(crate) struct HostCallbacks {
+    state: Arc<HostCallbacksState>,
+    // the lists point into the infos
+    _infos: (
+        Vec<Vec<PJRT_SendCallbackInfo>>,
+        Vec<Vec<PJRT_RecvCallbackInfo>>,
+    ),
+    send_lists: Vec<*mut PJRT_SendCallbackInfo>,
+    recv_lists: Vec<*mut PJRT_RecvCallbackInfo>,
+    num_send_ops: usize,
+    num_recv_ops: usize,
+}
+
+impl HostCallbacks {
+    pub(crate) fn set_options(&mut self, options: &mut PJRT_ExecuteOptions) {
+        if self.num_send_ops > 0 {
+            options.send_callbacks = self.send_lists.as_mut_ptr();
+            options.num_send_ops = self.num_send_ops;
+        }
+        if self.num_recv_ops > 0 {
+            options.recv_callbacks = self.recv_lists.as_mut_ptr();
+            options.num_recv_ops = self.num_recv_ops;
+        }
+    }
+
+    /// Keeps the callbacks alive until `event` is ready.
+    pub(crate) fn keep_alive(&self, event: &Event) {
+        if self.num_send_ops == 0 && self.num_recv_ops == 0 {
+            return;
+        }
+        if event.keep_alive(self.state.clone()).is_err() {
+            // we can't know when the execution is done, leak rather than free
+            // state PJRT may still call into
+            mem::forget(self.state.clone());
+        }
+    }
+}
+
+type CallbacksPerDevice<T> = Vec<Vec<(i64, T)>>;
+
+// groups callbacks by device, every device must have the same number of them
+fn callbacks_per_device<T: Clone>(
+    callbacks: &[(usize, i64, T)],
+    num_devices: usize,
+    kind: &str,
+) -> Result<(CallbacksPerDevice<T>, usize)> {
+    let mut per_device = vec![vec![]; num_devices];
+    for (device_index, channel_id, callback) in callbacks {
+        let Some(device) = per_device.get_mut(*device_index) else {
+            return Err(Error::InvalidHostCallbacks(format!(
+                "{} callback registered for device index {} but execution has {} devices",
+                kind, device_index, num_devices
+            )));
+        };
+        device.push((*channel_id, callback.clone()));
+    }
+    let num_ops = per_device.first().map(|d| d.len()).unwrap_or(0);
+    if per_device.iter().any(|d| d.len() != num_ops) {
+        return Err(Error::InvalidHostCallbacks(format!(
+            "every device must have the same number of {} callbacks",
+            kind
+        )));
+    }
+    Ok((per_device, num_ops))
+}
 
 pub struct ExecuteContext {
     api: Api,
@@ -37,10 +176,10 @@ impl ExecuteContext {
 
 pub struct ExecuteOptions {
     launch_id: i32,
-    non_donatable_input_indices: Vec<i64>,
-    // TODO:
-    // send_callbacks
-    // recv_callbacks
+    pub(crate) non_donatable_input_indices: Vec<i64>,
+    send_callbacks: Vec<(usize, i64, Arc<Mutex<SendCallback>>)>,
+    recv_callbacks: Vec<(usize, i64, Arc<Mutex<RecvCallback>>)>,
+    pub(crate) execute_device: Option<Device>,
 }
 
 impl ExecuteOptions {
@@ -48,6 +187,9 @@ impl ExecuteOptions {
         Self {
             launch_id: 0,
             non_donatable_input_indices: vec![],
+            send_callbacks: vec![],
+            recv_callbacks: vec![],
+            execute_device: None,
         }
     }
 
@@ -60,6 +202,101 @@ impl ExecuteOptions {
         self.non_donatable_input_indices = indices.into();
         self
     }
+
+    /// Launches a multi-device executable on `device` only, with a single
+    /// argument list. The caller is responsible for launching it on every
+    /// other participating device.
+    pub fn execute_device(mut self, device: &Device) -> Self {
+        self.execute_device = Some(Device::wrap(device.client(), device.ptr));
+        self
+    }
+
+    /// Registers a callback for the send op `channel_id` on the device at
+    /// `device_index` in the executable's addressable devices. Executions on a
+    /// single device, see [`Self::execute_device`], only have index 0.
+    ///
+    /// Every device of an execution must register the same number of send
+    /// callbacks.
+    pub fn send_callback<F>(mut self, device_index: usize, channel_id: i64, callback: F) -> Self
+    where
+        F: FnMut(Chunk, usize, bool) -> Result<()> + Send + 'static,
+    {
+        let callback: SendCallback = Box::new(callback);
+        self.send_callbacks
+            .push((device_index, channel_id, Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    /// Registers a callback for the recv op `channel_id` on the device at
+    /// `device_index` in the executable's addressable devices. Executions on a
+    /// single device, see [`Self::execute_device`], only have index 0.
+    ///
+    /// Every device of an execution must register the same number of recv
+    /// callbacks.
+    pub fn recv_callback<F>(mut self, device_index: usize, channel_id: i64, callback: F) -> Self
+    where
+        F: FnMut(CopyToDeviceStream) + Send + 'static,
+    {
+        let callback: RecvCallback = Box::new(callback);
+        self.recv_callbacks
+            .push((device_index, channel_id, Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    pub(crate) fn host_callbacks(&self, api: &Api, num_devices: usize) -> Result<HostCallbacks> {
+        let (send, num_send_ops) = callbacks_per_device(&self.send_callbacks, num_devices, "send")?;
+        let (recv, num_recv_ops) = callbacks_per_device(&self.recv_callbacks, num_devices, "recv")?;
+        let state: Arc<HostCallbacksState> = Arc::new((
+            self.send_callbacks.iter().map(|c| c.2.clone()).collect(),
+            recv.iter()
+                .flatten()
+                .map(|(_, callback)| RecvCallbackState {
+                    api: api.clone(),
+                    callback: callback.clone(),
+                })
+                .collect(),
+        ));
+        let mut send_infos: Vec<Vec<PJRT_SendCallbackInfo>> = send
+            .iter()
+            .map(|device| {
+                device
+                    .iter()
+                    .map(|(channel_id, callback)| PJRT_SendCallbackInfo {
+                        channel_id: *channel_id,
+                        user_arg: Arc::as_ptr(callback) as *mut c_void,
+                        send_callback: Some(send_callback),
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut recv_states = state.1.iter();
+        let mut recv_infos: Vec<Vec<PJRT_RecvCallbackInfo>> = recv
+            .iter()
+            .map(|device| {
+                device
+                    .iter()
+                    .map(|(channel_id, _)| {
+                        let state = recv_states.next().expect("recv callback state");
+                        PJRT_RecvCallbackInfo {
+                            channel_id: *channel_id,
+                            user_arg: state as *const RecvCallbackState as *mut c_void,
+                            recv_callback: Some(recv_callback),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let send_lists = send_infos.iter_mut().map(|d| d.as_mut_ptr()).collect();
+        let recv_lists = recv_infos.iter_mut().map(|d| d.as_mut_ptr()).collect();
+        Ok(HostCallbacks {
+            state,
+            _infos: (send_infos, recv_infos),
+            send_lists,
+            recv_lists,
+            num_send_ops,
+            num_recv_ops,
+        })
+    }
 }
 
 impl Default for ExecuteOptions {
@@ -89,14 +326,10 @@ where
     T: ExecutionInputs,
 {
     pub fn new(loaded_executable: &'a LoadedExecutable, inputs: T) -> Self {
-        let options = ExecuteOptions {
-            launch_id: 0,
-            non_donatable_input_indices: inputs.non_donatable_input_indices(),
-        };
         Self {
             loaded_executable,
             inputs,
-            options,
+            options: ExecuteOptions::new(),
         }
     }
 
@@ -110,24 +343,47 @@ where
         self
     }
 
-    pub async fn run(self) -> Result<Vec<Vec<Buffer>>> {
+    /// Runs only on `device`, which must be one of the executable's
+    /// addressable devices. The outputs hold that device's buffers only.
+    pub fn on_device(mut self, device: &Device) -> Self {
+        self.options = self.options.execute_device(device);
+        self
+    }
+
+    pub fn send_callback<F>(mut self, device_index: usize, channel_id: i64, callback: F) -> Self
+    where
+        F: FnMut(Chunk, usize, bool) -> Result<()> + Send + 'static,
+    {
+        self.options = self
+            .options
+            .send_callback(device_index, channel_id, callback);
+        self
+    }
+
+    pub fn recv_callback<F>(mut self, device_index: usize, channel_id: i64, callback: F) -> Self
+    where
+        F: FnMut(CopyToDeviceStream) + Send + 'static,
+    {
+        self.options = self
+            .options
+            .recv_callback(device_index, channel_id, callback);
+        self
+    }
+
+    pub async fn run(self) -> Result<Vec<ShapeTree<Buffer>>> {
         let (events, outputs) = self
             .loaded_executable
             .call_execute(self.inputs, &self.options)?;
-        for event in events {
-            event.await?;
-        }
-        Ok(outputs)
+        Event::join_all(events).await?;
+        self.loaded_executable.output_trees(outputs)
     }
 
-    pub fn run_sync(self) -> Result<Vec<Vec<Buffer>>> {
+    pub fn run_sync(self) -> Result<Vec<ShapeTree<Buffer>>> {
         let (events, outputs) = self
             .loaded_executable
             .call_execute(self.inputs, &self.options)?;
-        for event in events {
-            event.wait()?;
-        }
-        Ok(outputs)
+        Event::join_all(events).wait()?;
+        self.loaded_executable.output_trees(outputs)
     }
 }
 
@@ -138,41 +394,137 @@ pub trait ExecutionInputs {
     }
 }
 
+/// A single argument of an execution.
+///
+/// Arguments passed by reference are never donated. Arguments passed by value
+/// may be donated to the outputs, which deletes them, so they are consumed.
+pub trait ExecutionInput {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer;
+    fn donatable(&self) -> bool;
+}
+
+/// Donates `buffer` to the execution, the runtime may reuse its memory for
+/// the outputs. Passing a [`Buffer`] by value does the same.
+pub struct Donate(pub Buffer);
+
+impl ExecutionInput for Donate {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.0.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        true
+    }
+}
+
+impl ExecutionInput for Buffer {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        true
+    }
+}
+
+impl ExecutionInput for &Buffer {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        false
+    }
+}
+
+// borrowed buffers alias host memory, the runtime must not donate them to
+// outputs that could outlive the borrow. they are only passed by reference,
+// dropping one while the execution reads it would block the caller.
+impl ExecutionInput for &BorrowedBuffer<'_> {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        false
+    }
+}
+
+// takes whether each argument of each device is donatable, an argument
+// can't be donated if it is borrowed on any device
+fn non_donatable_indices<L, D>(lists: L) -> Vec<i64>
+where
+    L: IntoIterator<Item = D>,
+    D: IntoIterator<Item = bool>,
+{
+    let mut indices = Vec::new();
+    for list in lists {
+        for (index, donatable) in list.into_iter().enumerate() {
+            if !donatable {
+                indices.push(index as i64);
+            }
+        }
+    }
+    indices.sort();
+    indices.dedup();
+    indices
+}
+
+fn donatable<I: ExecutionInput>(list: &[I]) -> impl Iterator<Item = bool> + '_ {
+    list.iter().map(|input| input.donatable())
+}
+
 impl ExecutionInputs for () {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
         vec![vec![]]
     }
 }
 
-impl ExecutionInputs for Buffer {
+impl<I: ExecutionInput> ExecutionInputs for I {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
-        vec![vec![self.ptr]]
+        vec![vec![self.buffer_ptr()]]
+    }
+
+    fn non_donatable_input_indices(&self) -> Vec<i64> {
+        non_donatable_indices([[self.donatable()]])
     }
 }
 
-impl<const A: usize> ExecutionInputs for [Buffer; A] {
+impl<I: ExecutionInput, const A: usize> ExecutionInputs for [I; A] {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
-        vec![self.iter().map(|b| b.ptr).collect()]
+        vec![self.iter().map(|b| b.buffer_ptr()).collect()]
+    }
+
+    fn non_donatable_input_indices(&self) -> Vec<i64> {
+        non_donatable_indices([donatable(self)])
     }
 }
 
-impl<const D: usize, const A: usize> ExecutionInputs for [[Buffer; A]; D] {
+impl<I: ExecutionInput, const D: usize, const A: usize> ExecutionInputs for [[I; A]; D] {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
         let mut buffer_refs = Vec::with_capacity(D);
         for array in self.iter() {
-            buffer_refs.push(array.iter().map(|b| b.ptr).collect());
+            buffer_refs.push(array.iter().map(|b| b.buffer_ptr()).collect());
         }
         buffer_refs
     }
+
+    fn non_donatable_input_indices(&self) -> Vec<i64> {
+        non_donatable_indices(self.iter().map(|a| donatable(a)))
+    }
 }
 
-impl ExecutionInputs for Vec<Buffer> {
+impl<I: ExecutionInput> ExecutionInputs for Vec<I> {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
-        vec![self.iter().map(|b| b.ptr).collect()]
+        vec![self.iter().map(|b| b.buffer_ptr()).collect()]
+    }
+
+    fn non_donatable_input_indices(&self) -> Vec<i64> {
+        non_donatable_indices([donatable(self)])
     }
 }
 
-impl ExecutionInputs for Vec<Vec<Buffer>> {
+impl<I: ExecutionInput> ExecutionInputs for Vec<Vec<I>> {
     fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
         let inner_size = self.iter().fold(HashSet::new(), |mut set, buffers| {
             set.insert(buffers.len());
@@ -184,7 +536,35 @@ impl ExecutionInputs for Vec<Vec<Buffer>> {
             "all inner vectors must have the same length"
         );
         self.iter()
-            .map(|buffers| buffers.iter().map(|b| b.ptr).collect())
+            .map(|buffers| buffers.iter().map(|b| b.buffer_ptr()).collect())
             .collect()
     }
+
+    fn non_donatable_input_indices(&self) -> Vec<i64> {
+        non_donatable_indices(self.iter().map(|v| donatable(v)))
+    }
+}
+
+// tuples mix borrowed and donated arguments of a single device
+macro_rules! impl_execution_inputs_for_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: ExecutionInput),+> ExecutionInputs for ($($name,)+) {
+            fn buffer_ptrs(&self) -> Vec<Vec<*mut PJRT_Buffer>> {
+                vec![vec![$(self.$index.buffer_ptr()),+]]
+            }
+
+            fn non_donatable_input_indices(&self) -> Vec<i64> {
+                non_donatable_indices([[$(self.$index.donatable()),+]])
+            }
+        }
+    };
 }
+
+impl_execution_inputs_for_tuple!(A 0);
+impl_execution_inputs_for_tuple!(A 0, B 1);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2, D 3);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_execution_inputs_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
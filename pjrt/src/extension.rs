@@ -0,0 +1,116 @@
+use pjrt_sys::{
+    PJRT_Extension_Base, PJRT_Extension_Type,
+    PJRT_Extension_Type_PJRT_Extension_Type_Custom_Partitioner,
+    PJRT_Extension_Type_PJRT_Extension_Type_FFI,
+    PJRT_Extension_Type_PJRT_Extension_Type_Gpu_Custom_Call,
+    PJRT_Extension_Type_PJRT_Extension_Type_Layouts,
+    PJRT_Extension_Type_PJRT_Extension_Type_Profiler,
+    PJRT_Extension_Type_PJRT_Extension_Type_Stream,
+};
+
+use crate::Api;
+
+/// An extension supported by the loaded plugin, with its version.
+///
+/// Extensions have no explicit version number: new revisions only append
+/// fields, so the struct size reported by the plugin is used as version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Extension {
+    GpuCustomCall(usize),
+    Profiler(usize),
+    CustomPartitioner(usize),
+    Stream(usize),
+    Layouts(usize),
+    Ffi(usize),
+    Unknown(PJRT_Extension_Type, usize),
+}
+
+impl Extension {
+    #[allow(non_upper_case_globals)]
+    pub(crate) fn new(base: &PJRT_Extension_Base) -> Self {
+        let version = base.struct_size;
+        match base.type_ {
+            PJRT_Extension_Type_PJRT_Extension_Type_Gpu_Custom_Call => Self::GpuCustomCall(version),
+            PJRT_Extension_Type_PJRT_Extension_Type_Profiler => Self::Profiler(version),
+            PJRT_Extension_Type_PJRT_Extension_Type_Custom_Partitioner => {
+                Self::CustomPartitioner(version)
+            }
+            PJRT_Extension_Type_PJRT_Extension_Type_Stream => Self::Stream(version),
+            PJRT_Extension_Type_PJRT_Extension_Type_Layouts => Self::Layouts(version),
+            PJRT_Extension_Type_PJRT_Extension_Type_FFI => Self::Ffi(version),
+            ty => Self::Unknown(ty, version),
+        }
+    }
+
+    pub fn version(&self) -> usize {
+        match self {
+            Self::GpuCustomCall(v)
+            | Self::Profiler(v)
+            | Self::CustomPartitioner(v)
+            | Self::Stream(v)
+            | Self::Layouts(v)
+            | Self::Ffi(v)
+            | Self::Unknown(_, v) => *v,
+        }
+    }
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+/// An extension that can be looked up with [`Api::extension`]. Implemented
+/// by this crate only.
+pub trait ApiExtension: sealed::Sealed + Sized {
+    const EXTENSION_TYPE: PJRT_Extension_Type;
+
+    #[doc(hidden)]
+    fn wrap(api: &Api, ptr: *mut PJRT_Extension_Base) -> Self;
+}
+
+macro_rules! api_extension {
+    ($name:ident, $ty:ident) => {
+        pub struct $name {
+            api: Api,
+            #[allow(dead_code)]
+            pub(crate) ptr: *mut PJRT_Extension_Base,
+        }
+
+        impl $name {
+            pub fn api(&self) -> &Api {
+                &self.api
+            }
+
+            pub fn version(&self) -> usize {
+                unsafe { (*self.ptr).struct_size }
+            }
+        }
+
+        impl sealed::Sealed for $name {}
+
+        impl ApiExtension for $name {
+            const EXTENSION_TYPE: PJRT_Extension_Type = $ty;
+
+            fn wrap(api: &Api, ptr: *mut PJRT_Extension_Base) -> Self {
+                assert!(!ptr.is_null());
+                Self {
+                    api: api.clone(),
+                    ptr,
+                }
+            }
+        }
+    };
+}
+
+api_extension!(
+    GpuCustomCallExtension,
+    PJRT_Extension_Type_PJRT_Extension_Type_Gpu_Custom_Call
+);
+api_extension!(
+    CustomPartitionerExtension,
+    PJRT_Extension_Type_PJRT_Extension_Type_Custom_Partitioner
+);
+api_extension!(
+    StreamExtension,
+    PJRT_Extension_Type_PJRT_Extension_Type_Stream
+);
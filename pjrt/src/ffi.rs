@@ -0,0 +1,448 @@
+use std::ffi::{c_char, c_void, CString};
+use std::ops::{Deref, Range};
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use pjrt_sys::{
+    PJRT_Error, PJRT_Extension_Base, PJRT_Extension_Type,
+    PJRT_Extension_Type_PJRT_Extension_Type_FFI, PJRT_FFI_Extension,
+    PJRT_FFI_Extension_STRUCT_SIZE, PJRT_FFI_Register_Handler_Args, XLA_FFI_Api_Version,
+    XLA_FFI_ArgType, XLA_FFI_ArgType_XLA_FFI_ArgType_BUFFER, XLA_FFI_Array, XLA_FFI_AttrType,
+    XLA_FFI_AttrType_XLA_FFI_AttrType_ARRAY, XLA_FFI_AttrType_XLA_FFI_AttrType_DICTIONARY,
+    XLA_FFI_AttrType_XLA_FFI_AttrType_SCALAR, XLA_FFI_AttrType_XLA_FFI_AttrType_STRING,
+    XLA_FFI_Attrs, XLA_FFI_Buffer, XLA_FFI_ByteSpan, XLA_FFI_CallFrame, XLA_FFI_DataType,
+    XLA_FFI_DataType_XLA_FFI_DataType_BF16, XLA_FFI_DataType_XLA_FFI_DataType_C128,
+    XLA_FFI_DataType_XLA_FFI_DataType_C64, XLA_FFI_DataType_XLA_FFI_DataType_F16,
+    XLA_FFI_DataType_XLA_FFI_DataType_F32, XLA_FFI_DataType_XLA_FFI_DataType_F64,
+    XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3B11FNUZ, XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3FN,
+    XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3FNUZ, XLA_FFI_DataType_XLA_FFI_DataType_F8E5M2,
+    XLA_FFI_DataType_XLA_FFI_DataType_F8E5M2FNUZ, XLA_FFI_DataType_XLA_FFI_DataType_INVALID,
+    XLA_FFI_DataType_XLA_FFI_DataType_PRED, XLA_FFI_DataType_XLA_FFI_DataType_S16,
+    XLA_FFI_DataType_XLA_FFI_DataType_S32, XLA_FFI_DataType_XLA_FFI_DataType_S64,
+    XLA_FFI_DataType_XLA_FFI_DataType_S8, XLA_FFI_DataType_XLA_FFI_DataType_TOKEN,
+    XLA_FFI_DataType_XLA_FFI_DataType_U16, XLA_FFI_DataType_XLA_FFI_DataType_U32,
+    XLA_FFI_DataType_XLA_FFI_DataType_U64, XLA_FFI_DataType_XLA_FFI_DataType_U8, XLA_FFI_Error,
+    XLA_FFI_Error_Code, XLA_FFI_Error_Create_Args,
+    XLA_FFI_ExecutionStage_XLA_FFI_ExecutionStage_EXECUTE,
+    XLA_FFI_Extension_Type_XLA_FFI_Extension_Metadata, XLA_FFI_Metadata_Extension, XLA_FFI_RetType,
+    XLA_FFI_RetType_XLA_FFI_RetType_BUFFER, XLA_FFI_Scalar, XLA_FFI_API_MAJOR, XLA_FFI_API_MINOR,
+};
+
+use crate::extension::{sealed, ApiExtension};
+use crate::{utils, Api, ElemType, Error, PrimitiveType, Result, Type};
+
+pub struct FfiExtension {
+    api: Api,
+    ptr: *mut PJRT_FFI_Extension,
+}
+
+impl sealed::Sealed for FfiExtension {}
+
+impl ApiExtension for FfiExtension {
+    const EXTENSION_TYPE: PJRT_Extension_Type = PJRT_Extension_Type_PJRT_Extension_Type_FFI;
+
+    fn wrap(api: &Api, ptr: *mut PJRT_Extension_Base) -> Self {
+        assert!(!ptr.is_null());
+        Self {
+            api: api.clone(),
+            ptr: ptr as *mut PJRT_FFI_Extension,
+        }
+    }
+}
+
+macro_rules! ffi_fn_ret_err {
+    ($fn:ident, $field:ident, $args_ty:ident) => {
+        impl FfiExtension {
+            #[allow(non_snake_case)]
+            fn $fn(&self, mut args: pjrt_sys::$args_ty) -> Result<pjrt_sys::$args_ty> {
+                let func = unsafe { (*self.ptr).$field }
+                    .ok_or(Error::NullFunctionPointer(stringify!($fn)))?;
+                let err: *mut PJRT_Error = unsafe { func(&mut args as *mut _) };
+                self.api.err_or(err, args)
+            }
+        }
+    };
+}
+
+ffi_fn_ret_err!(
+    PJRT_FFI_Register_Handler,
+    register_handler,
+    PJRT_FFI_Register_Handler_Args
+);
+
+impl FfiExtension {
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    pub fn version(&self) -> usize {
+        unsafe { (*self.ptr).base.struct_size }
+    }
+
+    /// Registers `H` as the `stablehlo.custom_call` target `target_name` on
+    /// `platform_name`, e.g. `"Host"` for the cpu plugin or `"CUDA"`.
+    pub fn register_handler<H: FfiHandler>(
+        &self,
+        target_name: &str,
+        platform_name: &str,
+    ) -> Result<()> {
+        // handler registration was added in the second revision
+        if self.version() < PJRT_FFI_Extension_STRUCT_SIZE as usize {
+            return Err(Error::NullFunctionPointer("PJRT_FFI_Register_Handler"));
+        }
+        let mut args = PJRT_FFI_Register_Handler_Args::new();
+        args.target_name = target_name.as_ptr() as *const c_char;
+        args.target_name_size = target_name.len();
+        // typed ffi handler
+        args.api_version = 1;
+        args.handler = ffi_handler::<H> as *mut c_void;
+        args.platform_name = platform_name.as_ptr() as *const c_char;
+        args.platform_name_size = platform_name.len();
+        self.PJRT_FFI_Register_Handler(args)?;
+        Ok(())
+    }
+}
+
+/// A custom call target implemented in rust, see
+/// [`FfiExtension::register_handler`].
+pub trait FfiHandler: 'static {
+    fn execute(call_frame: &mut FfiCallFrame<'_>) -> Result<()>;
+}
+
+unsafe extern "C" fn ffi_handler<H: FfiHandler>(
+    call_frame: *mut XLA_FFI_CallFrame,
+) -> *mut XLA_FFI_Error {
+    let call_frame = unsafe { &*call_frame };
+    // on registration XLA asks the handler which ffi version it was built for
+    let extension = call_frame.extension_start;
+    if !extension.is_null()
+        && unsafe { (*extension).type_ } == XLA_FFI_Extension_Type_XLA_FFI_Extension_Metadata
+    {
+        let extension = extension as *mut XLA_FFI_Metadata_Extension;
+        let metadata = unsafe { &mut *(*extension).metadata };
+        let mut api_version = XLA_FFI_Api_Version::new();
+        api_version.major_version = XLA_FFI_API_MAJOR as i32;
+        api_version.minor_version = XLA_FFI_API_MINOR as i32;
+        metadata.api_version = api_version;
+        metadata.traits = 0;
+        return ptr::null_mut();
+    }
+    if call_frame.stage != XLA_FFI_ExecutionStage_XLA_FFI_ExecutionStage_EXECUTE {
+        return ptr::null_mut();
+    }
+    let mut frame = FfiCallFrame { raw: call_frame };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| H::execute(&mut frame)))
+        .unwrap_or(Err(Error::CallbackPanic));
+    match result {
+        Ok(()) => ptr::null_mut(),
+        Err(err) => frame.create_error(&err),
+    }
+}
+
+/// Operands, results and attributes of a custom call.
+pub struct FfiCallFrame<'a> {
+    raw: &'a XLA_FFI_CallFrame,
+}
+
+impl<'a> FfiCallFrame<'a> {
+    pub fn args(&self) -> Result<Vec<FfiBuffer<'a>>> {
+        let args = &self.raw.args;
+        (0..args.size as usize)
+            .map(|i| {
+                let ty: XLA_FFI_ArgType = unsafe { *args.types.add(i) };
+                if ty != XLA_FFI_ArgType_XLA_FFI_ArgType_BUFFER {
+                    return Err(Error::UnsupportedFfiType("argument", ty as i32));
+                }
+                let raw = unsafe { &*(*args.args.add(i) as *const XLA_FFI_Buffer) };
+                Ok(FfiBuffer { raw })
+            })
+            .collect()
+    }
+
+    /// Results of the custom call. A result that aliases an argument, e.g.
+    /// through `output_operand_aliases`, only exposes its raw pointer.
+    pub fn rets(&mut self) -> Result<Vec<FfiBufferMut<'_>>> {
+        let args = self.args()?;
+        let rets = &self.raw.rets;
+        (0..rets.size as usize)
+            .map(|i| {
+                let ty: XLA_FFI_RetType = unsafe { *rets.types.add(i) };
+                if ty != XLA_FFI_RetType_XLA_FFI_RetType_BUFFER {
+                    return Err(Error::UnsupportedFfiType("result", ty as i32));
+                }
+                let raw = unsafe { &*(*rets.rets.add(i) as *const XLA_FFI_Buffer) };
+                let buffer = FfiBuffer { raw };
+                let bytes = buffer.byte_range();
+                let aliased = args.iter().any(|arg| {
+                    let arg = arg.byte_range();
+                    arg.start < bytes.end && bytes.start < arg.end
+                });
+                Ok(FfiBufferMut { buffer, aliased })
+            })
+            .collect()
+    }
+
+    /// Attributes of the custom call, sorted by name.
+    pub fn attrs(&self) -> Result<Vec<FfiAttr>> {
+        unsafe { decode_attrs(&self.raw.attrs) }
+    }
+
+    pub fn attr(&self, name: &str) -> Result<Option<FfiAttrValue>> {
+        let attrs = self.attrs()?;
+        Ok(attrs.into_iter().find(|a| a.name == name).map(|a| a.value))
+    }
+
+    fn create_error(&self, err: &Error) -> *mut XLA_FFI_Error {
+        let message = CString::new(err.to_string().replace('\0', "")).unwrap_or_default();
+        let mut args = XLA_FFI_Error_Create_Args::new();
+        args.message = message.as_ptr();
+        args.errc = err.code() as XLA_FFI_Error_Code;
+        // panicking here would abort, there is no other way to report the error
+        match unsafe { (*self.raw.api).XLA_FFI_Error_Create } {
+            Some(create) => unsafe { create(&mut args) },
+            None => ptr::null_mut(),
+        }
+    }
+}
+
+/// A custom call operand.
+pub struct FfiBuffer<'a> {
+    raw: &'a XLA_FFI_Buffer,
+}
+
+impl<'a> FfiBuffer<'a> {
+    pub fn primitive_type(&self) -> Result<PrimitiveType> {
+        primitive_type(self.raw.dtype)
+    }
+
+    pub fn dims(&self) -> &'a [i64] {
+        if self.raw.rank == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.raw.dims, self.raw.rank as usize) }
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.dims().iter().product::<i64>() as usize
+    }
+
+    pub fn data(&self) -> *mut c_void {
+        self.raw.data
+    }
+
+    pub fn as_slice<T: ElemType>(&self) -> Result<&'a [T]> {
+        self.check_type::<T>()?;
+        let len = self.element_count();
+        if len == 0 {
+            return Ok(&[]);
+        }
+        Ok(unsafe { slice::from_raw_parts(self.raw.data as *const T, len) })
+    }
+
+    // the bytes the buffer spans, one per element when the size is unknown
+    fn byte_range(&self) -> Range<usize> {
+        let elem_size = self
+            .primitive_type()
+            .and_then(|ty| ty.try_into_dtype())
+            .map(|dtype| dtype.size())
+            .unwrap_or(1);
+        let start = self.raw.data as usize;
+        start..start + self.element_count() * elem_size
+    }
+
+    fn check_type<T: ElemType>(&self) -> Result<()> {
+        let actual = self.primitive_type()?;
+        let expected = T::Type::PRIMITIVE_TYPE;
+        if actual != expected {
+            return Err(Error::ElemTypeMismatch { expected, actual });
+        }
+        Ok(())
+    }
+}
+
+/// A custom call result, written by the handler.
+pub struct FfiBufferMut<'a> {
+    buffer: FfiBuffer<'a>,
+    aliased: bool,
+}
+
+impl<'a> Deref for FfiBufferMut<'a> {
+    type Target = FfiBuffer<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl FfiBufferMut<'_> {
+    /// Whether the result shares memory with an argument.
+    pub fn is_aliased(&self) -> bool {
+        self.aliased
+    }
+
+    /// Fails if the result aliases an argument, whose slices may be alive.
+    pub fn as_mut_slice<T: ElemType>(&mut self) -> Result<&mut [T]> {
+        if self.aliased {
+            return Err(Error::FfiError(
+                "result aliases an argument, use its data pointer".to_string(),
+            ));
+        }
+        self.buffer.check_type::<T>()?;
+        let len = self.buffer.element_count();
+        if len == 0 {
+            return Ok(&mut []);
+        }
+        Ok(unsafe { slice::from_raw_parts_mut(self.buffer.raw.data as *mut T, len) })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FfiAttr {
+    pub name: String,
+    pub value: FfiAttrValue,
+}
+
+/// Attribute value, integers are widened to 64 bits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FfiAttrValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    I64List(Vec<i64>),
+    U64List(Vec<u64>),
+    F32List(Vec<f32>),
+    F64List(Vec<f64>),
+    Dictionary(Vec<FfiAttr>),
+}
+
+#[allow(non_upper_case_globals)]
+unsafe fn decode_attrs(attrs: &XLA_FFI_Attrs) -> Result<Vec<FfiAttr>> {
+    (0..attrs.size as usize)
+        .map(|i| {
+            let name = unsafe { &**attrs.names.add(i) };
+            let name = utils::str_from_raw(name.ptr, name.len).into_owned();
+            let attr = unsafe { *attrs.attrs.add(i) };
+            let ty: XLA_FFI_AttrType = unsafe { *attrs.types.add(i) };
+            let value = match ty {
+                XLA_FFI_AttrType_XLA_FFI_AttrType_SCALAR => unsafe {
+                    decode_scalar(&*(attr as *const XLA_FFI_Scalar))?
+                },
+                XLA_FFI_AttrType_XLA_FFI_AttrType_ARRAY => unsafe {
+                    decode_array(&*(attr as *const XLA_FFI_Array))?
+                },
+                XLA_FFI_AttrType_XLA_FFI_AttrType_STRING => {
+                    let s = unsafe { &*(attr as *const XLA_FFI_ByteSpan) };
+                    FfiAttrValue::String(utils::str_from_raw(s.ptr, s.len).into_owned())
+                }
+                XLA_FFI_AttrType_XLA_FFI_AttrType_DICTIONARY => unsafe {
+                    FfiAttrValue::Dictionary(decode_attrs(&*(attr as *const XLA_FFI_Attrs))?)
+                },
+                ty => return Err(Error::UnsupportedFfiType("attribute", ty as i32)),
+            };
+            Ok(FfiAttr { name, value })
+        })
+        .collect()
+}
+
+#[allow(non_upper_case_globals)]
+unsafe fn decode_scalar(scalar: &XLA_FFI_Scalar) -> Result<FfiAttrValue> {
+    let value = scalar.value;
+    let value = unsafe {
+        match scalar.dtype {
+            XLA_FFI_DataType_XLA_FFI_DataType_PRED => FfiAttrValue::Bool(*(value as *const bool)),
+            XLA_FFI_DataType_XLA_FFI_DataType_S8 => FfiAttrValue::I64(*(value as *const i8) as i64),
+            XLA_FFI_DataType_XLA_FFI_DataType_S16 => {
+                FfiAttrValue::I64(*(value as *const i16) as i64)
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_S32 => {
+                FfiAttrValue::I64(*(value as *const i32) as i64)
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_S64 => FfiAttrValue::I64(*(value as *const i64)),
+            XLA_FFI_DataType_XLA_FFI_DataType_U8 => FfiAttrValue::U64(*(value as *const u8) as u64),
+            XLA_FFI_DataType_XLA_FFI_DataType_U16 => {
+                FfiAttrValue::U64(*(value as *const u16) as u64)
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_U32 => {
+                FfiAttrValue::U64(*(value as *const u32) as u64)
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_U64 => FfiAttrValue::U64(*(value as *const u64)),
+            XLA_FFI_DataType_XLA_FFI_DataType_F32 => FfiAttrValue::F32(*(value as *const f32)),
+            XLA_FFI_DataType_XLA_FFI_DataType_F64 => FfiAttrValue::F64(*(value as *const f64)),
+            dtype => return Err(Error::NotSupportedType(primitive_type(dtype)?)),
+        }
+    };
+    Ok(value)
+}
+
+unsafe fn array<T: Copy, U>(array: &XLA_FFI_Array, f: impl Fn(T) -> U) -> Vec<U> {
+    if array.size == 0 {
+        return vec![];
+    }
+    let data = unsafe { slice::from_raw_parts(array.data as *const T, array.size) };
+    data.iter().map(|v| f(*v)).collect()
+}
+
+#[allow(non_upper_case_globals)]
+unsafe fn decode_array(a: &XLA_FFI_Array) -> Result<FfiAttrValue> {
+    let value = unsafe {
+        match a.dtype {
+            XLA_FFI_DataType_XLA_FFI_DataType_S8 => {
+                FfiAttrValue::I64List(array(a, |v: i8| v as i64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_S16 => {
+                FfiAttrValue::I64List(array(a, |v: i16| v as i64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_S32 => {
+                FfiAttrValue::I64List(array(a, |v: i32| v as i64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_S64 => FfiAttrValue::I64List(array(a, |v: i64| v)),
+            XLA_FFI_DataType_XLA_FFI_DataType_U8 => {
+                FfiAttrValue::U64List(array(a, |v: u8| v as u64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_U16 => {
+                FfiAttrValue::U64List(array(a, |v: u16| v as u64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_U32 => {
+                FfiAttrValue::U64List(array(a, |v: u32| v as u64))
+            }
+            XLA_FFI_DataType_XLA_FFI_DataType_U64 => FfiAttrValue::U64List(array(a, |v: u64| v)),
+            XLA_FFI_DataType_XLA_FFI_DataType_F32 => FfiAttrValue::F32List(array(a, |v: f32| v)),
+            XLA_FFI_DataType_XLA_FFI_DataType_F64 => FfiAttrValue::F64List(array(a, |v: f64| v)),
+            dtype => return Err(Error::NotSupportedType(primitive_type(dtype)?)),
+        }
+    };
+    Ok(value)
+}
+
+// ffi data types follow the xla proto numbering, not the PJRT_Buffer_Type one
+#[allow(non_upper_case_globals)]
+fn primitive_type(dtype: XLA_FFI_DataType) -> Result<PrimitiveType> {
+    match dtype {
+        XLA_FFI_DataType_XLA_FFI_DataType_INVALID => Ok(PrimitiveType::Invalid),
+        XLA_FFI_DataType_XLA_FFI_DataType_PRED => Ok(PrimitiveType::Pred),
+        XLA_FFI_DataType_XLA_FFI_DataType_S8 => Ok(PrimitiveType::S8),
+        XLA_FFI_DataType_XLA_FFI_DataType_S16 => Ok(PrimitiveType::S16),
+        XLA_FFI_DataType_XLA_FFI_DataType_S32 => Ok(PrimitiveType::S32),
+        XLA_FFI_DataType_XLA_FFI_DataType_S64 => Ok(PrimitiveType::S64),
+        XLA_FFI_DataType_XLA_FFI_DataType_U8 => Ok(PrimitiveType::U8),
+        XLA_FFI_DataType_XLA_FFI_DataType_U16 => Ok(PrimitiveType::U16),
+        XLA_FFI_DataType_XLA_FFI_DataType_U32 => Ok(PrimitiveType::U32),
+        XLA_FFI_DataType_XLA_FFI_DataType_U64 => Ok(PrimitiveType::U64),
+        XLA_FFI_DataType_XLA_FFI_DataType_F16 => Ok(PrimitiveType::F16),
+        XLA_FFI_DataType_XLA_FFI_DataType_F32 => Ok(PrimitiveType::F32),
+        XLA_FFI_DataType_XLA_FFI_DataType_F64 => Ok(PrimitiveType::F64),
+        XLA_FFI_DataType_XLA_FFI_DataType_BF16 => Ok(PrimitiveType::BF16),
+        XLA_FFI_DataType_XLA_FFI_DataType_C64 => Ok(PrimitiveType::C64),
+        XLA_FFI_DataType_XLA_FFI_DataType_C128 => Ok(PrimitiveType::C128),
+        XLA_FFI_DataType_XLA_FFI_DataType_TOKEN => Ok(PrimitiveType::Token),
+        XLA_FFI_DataType_XLA_FFI_DataType_F8E5M2 => Ok(PrimitiveType::F8E5M2),
+        XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3FN => Ok(PrimitiveType::F8E4M3FN),
+        XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3B11FNUZ => Ok(PrimitiveType::F8E4M3B11FNUZ),
+        XLA_FFI_DataType_XLA_FFI_DataType_F8E5M2FNUZ => Ok(PrimitiveType::F8E5M2FNUZ),
+        XLA_FFI_DataType_XLA_FFI_DataType_F8E4M3FNUZ => Ok(PrimitiveType::F8E4M3FNUZ),
+        _ => Err(Error::InvalidPrimitiveType(dtype as i32)),
+    }
+}
@@ -1,6 +1,8 @@
+use std::any::Any;
 use std::ffi::c_void;
+use std::marker::PhantomData;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use bon::bon;
 use pjrt_sys::{
@@ -14,13 +16,14 @@ use pjrt_sys::{
 
 use crate::event::Event;
 use crate::{
-    utils, Buffer, Client, Device, ElemType, Error, Memory, MemoryLayout, PrimitiveType, Result,
-    Type, BF16, C128, C64, F16, F32, F64, I16, I32, I64, I8, U16, U32, U64, U8,
+    utils, Bool, Buffer, Client, Device, ElemType, Error, Executable, Memory, MemoryLayout,
+    PackedType, PrimitiveType, Result, Type, BF16, C128, C64, F16, F32, F64, F8E4M3B11FNUZ,
+    F8E4M3FN, F8E4M3FNUZ, F8E5M2, F8E5M2FNUZ, I16, I2, I32, I4, I64, I8, U16, U2, U32, U4, U64, U8,
 };
 
 #[derive(Debug)]
 pub struct TypedHostBuffer<T: Type> {
-    data: Rc<Vec<T::ElemType>>,
+    data: Arc<Vec<T::ElemType>>,
     dims: Vec<i64>,
     layout: MemoryLayout,
 }
@@ -37,7 +40,7 @@ impl<T: Type> TypedHostBuffer<T> {
         let layout = layout
             .unwrap_or_else(|| MemoryLayout::from_strides(utils::byte_strides(&dims, T::SIZE)));
         Self {
-            data: Rc::new(data),
+            data: Arc::new(data),
             dims,
             layout,
         }
@@ -59,7 +62,7 @@ impl<T: Type> TypedHostBuffer<T> {
         let layout = layout
             .unwrap_or_else(|| MemoryLayout::from_strides(utils::byte_strides(&dims, T::SIZE)));
         Self {
-            data: Rc::new(data),
+            data: Arc::new(data),
             dims,
             layout,
         }
@@ -70,7 +73,7 @@ impl<T: Type> TypedHostBuffer<T> {
         let dims = vec![];
         let layout = MemoryLayout::from_strides(vec![]);
         Self {
-            data: Rc::new(data),
+            data: Arc::new(data),
             dims,
             layout,
         }
@@ -97,25 +100,15 @@ impl<T: Type> TypedHostBuffer<T> {
     where
         D: HostBufferCopyToDest,
     {
-        let client = dest.client();
-        let mut args = PJRT_Client_BufferFromHostBuffer_Args::new();
-        args.client = client.ptr();
-        args.data = self.data.as_ptr() as *const c_void;
-        args.type_ = T::PRIMITIVE_TYPE as PJRT_Buffer_Type;
-        args.dims = self.dims.as_ptr();
-        args.num_dims = self.dims.len();
-        args.host_buffer_semantics =
-            HostBufferSemantics::ImmutableUntilTransferCompletes as PJRT_HostBufferSemantics;
-        if let Some(byte_strides) = &byte_strides {
-            args.byte_strides = byte_strides.as_ptr() as *const _;
-            args.num_byte_strides = byte_strides.len();
-        }
-        if let Some(device_layout) = &device_layout {
-            let mut device_layout = PJRT_Buffer_MemoryLayout::from(device_layout);
-            args.device_layout = &mut device_layout as *mut _;
-        }
-        dest.set_args(&mut args)?;
-        client.api().PJRT_Client_BufferFromHostBuffer(args)
+        buffer_from_host(
+            dest,
+            self.data.as_ptr() as *const c_void,
+            T::PRIMITIVE_TYPE,
+            &self.dims,
+            byte_strides.as_deref(),
+            device_layout.as_ref(),
+            HostBufferSemantics::ImmutableUntilTransferCompletes,
+        )
     }
 
     #[builder(finish_fn = copy)]
@@ -124,10 +117,14 @@ impl<T: Type> TypedHostBuffer<T> {
         #[builder(start_fn)] dest: &D,
         byte_strides: Option<Vec<i64>>,
         device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
     ) -> Result<Buffer>
     where
         D: HostBufferCopyToDest,
     {
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
         let args = self.call_copy_to(dest, byte_strides, device_layout)?;
         let done_with_host_event = Event::wrap(dest.client().api(), args.done_with_host_buffer);
         done_with_host_event.wait()?;
@@ -143,10 +140,14 @@ impl<T: Type> TypedHostBuffer<T> {
         #[builder(start_fn)] dest: &D,
         byte_strides: Option<Vec<i64>>,
         device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
     ) -> Result<Buffer>
     where
         D: HostBufferCopyToDest,
     {
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
         let args = self.call_copy_to(dest, byte_strides, device_layout)?;
         let done_with_host_event = Event::wrap(dest.client().api(), args.done_with_host_buffer);
         done_with_host_event.await?;
@@ -157,6 +158,168 @@ impl<T: Type> TypedHostBuffer<T> {
     }
 }
 
+/// A host buffer of a [`PackedType`], `data` holds the packed elements.
+#[derive(Debug)]
+pub struct PackedHostBuffer<T: PackedType> {
+    data: Arc<Vec<u8>>,
+    dims: Vec<i64>,
+    layout: MemoryLayout,
+    ty: PhantomData<T>,
+}
+
+#[bon]
+impl<T: PackedType> PackedHostBuffer<T> {
+    #[builder(finish_fn = build)]
+    pub fn from_data(
+        #[builder(start_fn, into)] data: Vec<T::ElemType>,
+        #[builder(into)] dims: Option<Vec<i64>>,
+        #[builder] layout: Option<MemoryLayout>,
+    ) -> Result<Self> {
+        let dims = slice_dims(dims, data.len())?;
+        let layout = layout.unwrap_or_else(|| packed_layout::<T>(&dims));
+        Ok(Self {
+            data: Arc::new(pack::<T>(&data)),
+            dims,
+            layout,
+            ty: PhantomData,
+        })
+    }
+
+    /// Creates a buffer from packed `bytes`. Given `dims`, `bytes` may also
+    /// hold one element per byte, as PJRT returns them.
+    #[builder(finish_fn = build)]
+    pub fn from_bytes(
+        #[builder(start_fn, into)] bytes: Vec<u8>,
+        #[builder(into)] dims: Option<Vec<i64>>,
+        #[builder] layout: Option<MemoryLayout>,
+    ) -> Result<Self> {
+        let dims = dims.unwrap_or_else(|| vec![(bytes.len() * T::PER_BYTE) as i64]);
+        let count = dims.iter().product::<i64>() as usize;
+        let data = if bytes.len() == count.div_ceil(T::PER_BYTE) {
+            bytes
+        } else if bytes.len() == count {
+            pack::<T>(&bytes.iter().map(|b| T::unpack(*b)).collect::<Vec<_>>())
+        } else {
+            return Err(Error::ElementCountMismatch {
+                dims,
+                len: bytes.len(),
+            });
+        };
+        let layout = layout.unwrap_or_else(|| packed_layout::<T>(&dims));
+        Ok(Self {
+            data: Arc::new(data),
+            dims,
+            layout,
+            ty: PhantomData,
+        })
+    }
+
+    /// The packed elements.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Unpacks the elements.
+    pub fn values(&self) -> Vec<T::ElemType> {
+        let count = self.dims.iter().product::<i64>() as usize;
+        (0..count)
+            .map(|i| T::unpack(self.data[i / T::PER_BYTE] >> (T::BITS * (i % T::PER_BYTE))))
+            .collect()
+    }
+
+    pub fn dims(&self) -> &[i64] {
+        &self.dims
+    }
+
+    pub fn layout(&self) -> &MemoryLayout {
+        &self.layout
+    }
+
+    pub(crate) fn call_copy_to<D>(
+        &self,
+        dest: &D,
+        byte_strides: Option<Vec<i64>>,
+        device_layout: Option<MemoryLayout>,
+    ) -> Result<PJRT_Client_BufferFromHostBuffer_Args>
+    where
+        D: HostBufferCopyToDest,
+    {
+        // PJRT takes one element per byte, the unpacked copy only has to live
+        // for the call
+        let unpacked = self.values().into_iter().map(T::pack).collect::<Vec<u8>>();
+        buffer_from_host(
+            dest,
+            unpacked.as_ptr() as *const c_void,
+            T::PRIMITIVE_TYPE,
+            &self.dims,
+            byte_strides.as_deref(),
+            device_layout.as_ref(),
+            HostBufferSemantics::ImmutableOnlyDuringCall,
+        )
+    }
+
+    #[builder(finish_fn = copy)]
+    pub fn to_sync<D>(
+        &self,
+        #[builder(start_fn)] dest: &D,
+        byte_strides: Option<Vec<i64>>,
+        device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
+    ) -> Result<Buffer>
+    where
+        D: HostBufferCopyToDest,
+    {
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
+        let args = self.call_copy_to(dest, byte_strides, device_layout)?;
+        let done_with_host_event = Event::wrap(dest.client().api(), args.done_with_host_buffer);
+        done_with_host_event.wait()?;
+        let buf = Buffer::wrap(dest.client(), args.buffer);
+        let buf_ready_event = buf.ready_event()?;
+        buf_ready_event.wait()?;
+        Ok(buf)
+    }
+
+    #[builder(finish_fn = copy)]
+    pub async fn to<D>(
+        &self,
+        #[builder(start_fn)] dest: &D,
+        byte_strides: Option<Vec<i64>>,
+        device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
+    ) -> Result<Buffer>
+    where
+        D: HostBufferCopyToDest,
+    {
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
+        let args = self.call_copy_to(dest, byte_strides, device_layout)?;
+        let done_with_host_event = Event::wrap(dest.client().api(), args.done_with_host_buffer);
+        done_with_host_event.await?;
+        let buf = Buffer::wrap(dest.client(), args.buffer);
+        let buf_ready_event = buf.ready_event()?;
+        buf_ready_event.await?;
+        Ok(buf)
+    }
+}
+
+// dense and row major, several elements per byte
+fn packed_layout<T: PackedType>(dims: &[i64]) -> MemoryLayout {
+    MemoryLayout::from_tiled((0..dims.len() as i64).rev().collect::<Vec<_>>())
+        .element_size_bits(T::BITS as i64)
+        .build()
+}
+
+fn pack<T: PackedType>(values: &[T::ElemType]) -> Vec<u8> {
+    let mut data = vec![0u8; values.len().div_ceil(T::PER_BYTE)];
+    for (i, value) in values.iter().enumerate() {
+        data[i / T::PER_BYTE] |= T::pack(*value) << (T::BITS * (i % T::PER_BYTE));
+    }
+    data
+}
+
 macro_rules! impl_from_typed_buffer {
     ($T:ident) => {
         impl From<TypedHostBuffer<$T>> for HostBuffer {
@@ -181,6 +344,27 @@ impl_from_typed_buffer![U32];
 impl_from_typed_buffer![U64];
 impl_from_typed_buffer![C64];
 impl_from_typed_buffer![C128];
+impl_from_typed_buffer![Bool];
+impl_from_typed_buffer![F8E5M2];
+impl_from_typed_buffer![F8E4M3FN];
+impl_from_typed_buffer![F8E4M3B11FNUZ];
+impl_from_typed_buffer![F8E5M2FNUZ];
+impl_from_typed_buffer![F8E4M3FNUZ];
+
+macro_rules! impl_from_packed_buffer {
+    ($T:ident) => {
+        impl From<PackedHostBuffer<$T>> for HostBuffer {
+            fn from(buf: PackedHostBuffer<$T>) -> Self {
+                Self::$T(buf)
+            }
+        }
+    };
+}
+
+impl_from_packed_buffer![I4];
+impl_from_packed_buffer![U4];
+impl_from_packed_buffer![I2];
+impl_from_packed_buffer![U2];
 
 #[derive(Debug)]
 pub enum HostBuffer {
@@ -198,6 +382,16 @@ pub enum HostBuffer {
     U64(TypedHostBuffer<U64>),
     C64(TypedHostBuffer<C64>),
     C128(TypedHostBuffer<C128>),
+    Bool(TypedHostBuffer<Bool>),
+    F8E5M2(TypedHostBuffer<F8E5M2>),
+    F8E4M3FN(TypedHostBuffer<F8E4M3FN>),
+    F8E4M3B11FNUZ(TypedHostBuffer<F8E4M3B11FNUZ>),
+    F8E5M2FNUZ(TypedHostBuffer<F8E5M2FNUZ>),
+    F8E4M3FNUZ(TypedHostBuffer<F8E4M3FNUZ>),
+    I4(PackedHostBuffer<I4>),
+    U4(PackedHostBuffer<U4>),
+    I2(PackedHostBuffer<I2>),
+    U2(PackedHostBuffer<U2>),
 }
 
 #[bon]
@@ -311,6 +505,67 @@ impl HostBuffer {
                     .maybe_layout(layout)
                     .build(),
             )),
+            // any non zero byte is true, reading them as bool directly would be ub
+            PrimitiveType::Pred => Ok(Self::Bool(
+                TypedHostBuffer::from_data(bytes.iter().map(|b| *b != 0).collect::<Vec<_>>())
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::F8E5M2 => Ok(Self::F8E5M2(
+                TypedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::F8E4M3FN => Ok(Self::F8E4M3FN(
+                TypedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::F8E4M3B11FNUZ => Ok(Self::F8E4M3B11FNUZ(
+                TypedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::F8E5M2FNUZ => Ok(Self::F8E5M2FNUZ(
+                TypedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::F8E4M3FNUZ => Ok(Self::F8E4M3FNUZ(
+                TypedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build(),
+            )),
+            PrimitiveType::S4 => Ok(Self::I4(
+                PackedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build()?,
+            )),
+            PrimitiveType::U4 => Ok(Self::U4(
+                PackedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build()?,
+            )),
+            PrimitiveType::S2 => Ok(Self::I2(
+                PackedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build()?,
+            )),
+            PrimitiveType::U2 => Ok(Self::U2(
+                PackedHostBuffer::from_bytes(bytes)
+                    .maybe_dims(dims)
+                    .maybe_layout(layout)
+                    .build()?,
+            )),
             _ => Err(Error::NotSupportedType(ty)),
         }
     }
@@ -324,6 +579,98 @@ impl HostBuffer {
         Self::from(buf)
     }
 
+    pub fn primitive_type(&self) -> PrimitiveType {
+        match self {
+            Self::BF16(_) => BF16::PRIMITIVE_TYPE,
+            Self::F16(_) => F16::PRIMITIVE_TYPE,
+            Self::F32(_) => F32::PRIMITIVE_TYPE,
+            Self::F64(_) => F64::PRIMITIVE_TYPE,
+            Self::I8(_) => I8::PRIMITIVE_TYPE,
+            Self::I16(_) => I16::PRIMITIVE_TYPE,
+            Self::I32(_) => I32::PRIMITIVE_TYPE,
+            Self::I64(_) => I64::PRIMITIVE_TYPE,
+            Self::U8(_) => U8::PRIMITIVE_TYPE,
+            Self::U16(_) => U16::PRIMITIVE_TYPE,
+            Self::U32(_) => U32::PRIMITIVE_TYPE,
+            Self::U64(_) => U64::PRIMITIVE_TYPE,
+            Self::C64(_) => C64::PRIMITIVE_TYPE,
+            Self::C128(_) => C128::PRIMITIVE_TYPE,
+            Self::Bool(_) => Bool::PRIMITIVE_TYPE,
+            Self::F8E5M2(_) => F8E5M2::PRIMITIVE_TYPE,
+            Self::F8E4M3FN(_) => F8E4M3FN::PRIMITIVE_TYPE,
+            Self::F8E4M3B11FNUZ(_) => F8E4M3B11FNUZ::PRIMITIVE_TYPE,
+            Self::F8E5M2FNUZ(_) => F8E5M2FNUZ::PRIMITIVE_TYPE,
+            Self::F8E4M3FNUZ(_) => F8E4M3FNUZ::PRIMITIVE_TYPE,
+            Self::I4(_) => I4::PRIMITIVE_TYPE,
+            Self::U4(_) => U4::PRIMITIVE_TYPE,
+            Self::I2(_) => I2::PRIMITIVE_TYPE,
+            Self::U2(_) => U2::PRIMITIVE_TYPE,
+        }
+    }
+
+    /// The buffer as a [`TypedHostBuffer<T>`], if it holds elements of `T`.
+    pub fn as_typed<T: Type>(&self) -> Option<&TypedHostBuffer<T>> {
+        let buf: &dyn Any = match self {
+            Self::BF16(buf) => buf,
+            Self::F16(buf) => buf,
+            Self::F32(buf) => buf,
+            Self::F64(buf) => buf,
+            Self::I8(buf) => buf,
+            Self::I16(buf) => buf,
+            Self::I32(buf) => buf,
+            Self::I64(buf) => buf,
+            Self::U8(buf) => buf,
+            Self::U16(buf) => buf,
+            Self::U32(buf) => buf,
+            Self::U64(buf) => buf,
+            Self::C64(buf) => buf,
+            Self::C128(buf) => buf,
+            Self::Bool(buf) => buf,
+            Self::F8E5M2(buf) => buf,
+            Self::F8E4M3FN(buf) => buf,
+            Self::F8E4M3B11FNUZ(buf) => buf,
+            Self::F8E5M2FNUZ(buf) => buf,
+            Self::F8E4M3FNUZ(buf) => buf,
+            Self::I4(_) => return None,
+            Self::U4(_) => return None,
+            Self::I2(_) => return None,
+            Self::U2(_) => return None,
+        };
+        buf.downcast_ref()
+    }
+
+    /// Converts the buffer into a [`TypedHostBuffer<T>`], if it holds
+    /// elements of `T`.
+    pub fn into_typed<T: Type>(self) -> Option<TypedHostBuffer<T>> {
+        let buf: Box<dyn Any> = match self {
+            Self::BF16(buf) => Box::new(buf),
+            Self::F16(buf) => Box::new(buf),
+            Self::F32(buf) => Box::new(buf),
+            Self::F64(buf) => Box::new(buf),
+            Self::I8(buf) => Box::new(buf),
+            Self::I16(buf) => Box::new(buf),
+            Self::I32(buf) => Box::new(buf),
+            Self::I64(buf) => Box::new(buf),
+            Self::U8(buf) => Box::new(buf),
+            Self::U16(buf) => Box::new(buf),
+            Self::U32(buf) => Box::new(buf),
+            Self::U64(buf) => Box::new(buf),
+            Self::C64(buf) => Box::new(buf),
+            Self::C128(buf) => Box::new(buf),
+            Self::Bool(buf) => Box::new(buf),
+            Self::F8E5M2(buf) => Box::new(buf),
+            Self::F8E4M3FN(buf) => Box::new(buf),
+            Self::F8E4M3B11FNUZ(buf) => Box::new(buf),
+            Self::F8E5M2FNUZ(buf) => Box::new(buf),
+            Self::F8E4M3FNUZ(buf) => Box::new(buf),
+            Self::I4(_) => return None,
+            Self::U4(_) => return None,
+            Self::I2(_) => return None,
+            Self::U2(_) => return None,
+        };
+        buf.downcast().ok().map(|buf| *buf)
+    }
+
     pub fn dims(&self) -> &[i64] {
         match self {
             Self::BF16(buf) => buf.dims(),
@@ -340,6 +687,16 @@ impl HostBuffer {
             Self::U64(buf) => buf.dims(),
             Self::C64(buf) => buf.dims(),
             Self::C128(buf) => buf.dims(),
+            Self::Bool(buf) => buf.dims(),
+            Self::F8E5M2(buf) => buf.dims(),
+            Self::F8E4M3FN(buf) => buf.dims(),
+            Self::F8E4M3B11FNUZ(buf) => buf.dims(),
+            Self::F8E5M2FNUZ(buf) => buf.dims(),
+            Self::F8E4M3FNUZ(buf) => buf.dims(),
+            Self::I4(buf) => buf.dims(),
+            Self::U4(buf) => buf.dims(),
+            Self::I2(buf) => buf.dims(),
+            Self::U2(buf) => buf.dims(),
         }
     }
 
@@ -359,6 +716,16 @@ impl HostBuffer {
             Self::U64(buf) => buf.layout(),
             Self::C64(buf) => buf.layout(),
             Self::C128(buf) => buf.layout(),
+            Self::Bool(buf) => buf.layout(),
+            Self::F8E5M2(buf) => buf.layout(),
+            Self::F8E4M3FN(buf) => buf.layout(),
+            Self::F8E4M3B11FNUZ(buf) => buf.layout(),
+            Self::F8E5M2FNUZ(buf) => buf.layout(),
+            Self::F8E4M3FNUZ(buf) => buf.layout(),
+            Self::I4(buf) => buf.layout(),
+            Self::U4(buf) => buf.layout(),
+            Self::I2(buf) => buf.layout(),
+            Self::U2(buf) => buf.layout(),
         }
     }
     pub(crate) fn call_copy_to<D>(
@@ -385,6 +752,16 @@ impl HostBuffer {
             Self::U64(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
             Self::C64(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
             Self::C128(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::Bool(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::F8E5M2(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::F8E4M3FN(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::F8E4M3B11FNUZ(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::F8E5M2FNUZ(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::F8E4M3FNUZ(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::I4(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::U4(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::I2(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
+            Self::U2(buf) => buf.call_copy_to(dest, byte_strides, device_layout),
         }
     }
 
@@ -394,11 +771,15 @@ impl HostBuffer {
         #[builder(start_fn)] dest: &D,
         byte_strides: Option<Vec<i64>>,
         device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
     ) -> Result<Buffer>
     where
         D: HostBufferCopyToDest,
     {
         let client = dest.client().clone();
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
         let args = self.call_copy_to(dest, byte_strides, device_layout)?;
         let done_with_host_event = Event::wrap(client.api(), args.done_with_host_buffer);
         done_with_host_event.wait()?;
@@ -414,11 +795,15 @@ impl HostBuffer {
         #[builder(start_fn)] dest: &D,
         byte_strides: Option<Vec<i64>>,
         device_layout: Option<MemoryLayout>,
+        /// Uses the device layout the executable expects for the argument at
+        /// this index, see [`Executable::parameter_layout`].
+        executable_parameter: Option<(&Executable, usize)>,
     ) -> Result<Buffer>
     where
         D: HostBufferCopyToDest,
     {
         let client = dest.client().clone();
+        let device_layout = device_layout_for(device_layout, executable_parameter)?;
         let args = self.call_copy_to(dest, byte_strides, device_layout)?;
         let done_with_host_event = Event::wrap(client.api(), args.done_with_host_buffer);
         done_with_host_event.await?;
@@ -431,7 +816,6 @@ impl HostBuffer {
 
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[allow(dead_code)]
 pub enum HostBufferSemantics {
     /// The runtime may not hold references to `data` after the call to
     /// `PJRT_Client_BufferFromHostBuffer` completes. The caller promises that
@@ -469,6 +853,59 @@ pub enum HostBufferSemantics {
     MutableZeroCopy = PJRT_HostBufferSemantics_PJRT_HostBufferSemantics_kMutableZeroCopy as i32,
 }
 
+pub(crate) fn buffer_from_host<D>(
+    dest: &D,
+    data: *const c_void,
+    ty: PrimitiveType,
+    dims: &[i64],
+    byte_strides: Option<&[i64]>,
+    device_layout: Option<&MemoryLayout>,
+    semantics: HostBufferSemantics,
+) -> Result<PJRT_Client_BufferFromHostBuffer_Args>
+where
+    D: HostBufferCopyToDest,
+{
+    let client = dest.client();
+    let mut args = PJRT_Client_BufferFromHostBuffer_Args::new();
+    args.client = client.ptr();
+    args.data = data;
+    args.type_ = ty as PJRT_Buffer_Type;
+    args.dims = dims.as_ptr();
+    args.num_dims = dims.len();
+    args.host_buffer_semantics = semantics as PJRT_HostBufferSemantics;
+    if let Some(byte_strides) = byte_strides {
+        args.byte_strides = byte_strides.as_ptr() as *const _;
+        args.num_byte_strides = byte_strides.len();
+    }
+    let mut device_layout = device_layout.map(PJRT_Buffer_MemoryLayout::from);
+    if let Some(device_layout) = device_layout.as_mut() {
+        args.device_layout = device_layout as *mut _;
+    }
+    dest.set_args(&mut args)?;
+    client.api().PJRT_Client_BufferFromHostBuffer(args)
+}
+
+// dims default to a vector of the slice, otherwise they must cover it exactly
+pub(crate) fn slice_dims(dims: Option<Vec<i64>>, len: usize) -> Result<Vec<i64>> {
+    let dims = dims.unwrap_or_else(|| vec![len as i64]);
+    if dims.iter().product::<i64>() != len as i64 {
+        return Err(Error::ElementCountMismatch { dims, len });
+    }
+    Ok(dims)
+}
+
+// an explicit device layout wins over the one the executable was compiled for
+fn device_layout_for(
+    device_layout: Option<MemoryLayout>,
+    executable_parameter: Option<(&Executable, usize)>,
+) -> Result<Option<MemoryLayout>> {
+    match (device_layout, executable_parameter) {
+        (Some(layout), _) => Ok(Some(layout)),
+        (None, Some((executable, index))) => Ok(Some(executable.parameter_layout(index)?)),
+        (None, None) => Ok(None),
+    }
+}
+
 pub(crate) trait HostBufferCopyToDest {
     fn client(&self) -> &Client;
     fn set_args(&self, args: &mut PJRT_Client_BufferFromHostBuffer_Args) -> Result<()>;
@@ -481,7 +918,7 @@ impl HostBufferCopyToDest for Client {
 
     fn set_args(&self, args: &mut PJRT_Client_BufferFromHostBuffer_Args) -> Result<()> {
         args.device = self
-            .addressable_devices()
+            .try_addressable_devices()?
             .first()
             .ok_or(Error::NoAddressableDevice)?
             .ptr;
@@ -510,3 +947,77 @@ impl HostBufferCopyToDest for Memory {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{I2, I4, U2, U4};
+
+    #[test]
+    fn pack_unpack() {
+        let buf = PackedHostBuffer::<I4>::from_data(vec![1i8, -1, 7, -8, 3])
+            .build()
+            .unwrap();
+        assert_eq!(buf.data(), [0xf1, 0x87, 0x03]);
+        assert_eq!(buf.values(), [1, -1, 7, -8, 3]);
+        assert_eq!(buf.dims(), [5]);
+
+        let buf = PackedHostBuffer::<U4>::from_data(vec![1u8, 15, 16])
+            .build()
+            .unwrap();
+        // values are truncated to their lowest bits
+        assert_eq!(buf.values(), [1, 15, 0]);
+
+        let buf = PackedHostBuffer::<I2>::from_data(vec![0i8, 1, -1, -2, 1])
+            .dims([5])
+            .build()
+            .unwrap();
+        assert_eq!(buf.data(), [0b10_11_01_00, 0b01]);
+        assert_eq!(buf.values(), [0, 1, -1, -2, 1]);
+
+        let buf = PackedHostBuffer::<U2>::from_data(vec![3u8, 2, 1, 0])
+            .build()
+            .unwrap();
+        assert_eq!(buf.data(), [0b00_01_10_11]);
+        assert_eq!(buf.values(), [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn from_bytes() {
+        // packed
+        let buf = PackedHostBuffer::<I4>::from_bytes(vec![0xf1, 0x07])
+            .dims([3])
+            .build()
+            .unwrap();
+        assert_eq!(buf.values(), [1, -1, 7]);
+        // one element per byte, as PJRT returns them
+        let buf = PackedHostBuffer::<I4>::from_bytes(vec![0x01, 0x0f, 0x07])
+            .dims([3])
+            .build()
+            .unwrap();
+        assert_eq!(buf.data(), [0xf1, 0x07]);
+        assert_eq!(buf.values(), [1, -1, 7]);
+        // neither
+        assert!(matches!(
+            PackedHostBuffer::<I4>::from_bytes(vec![0; 5])
+                .dims([3])
+                .build(),
+            Err(Error::ElementCountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn packed_default_layout() {
+        let buf = PackedHostBuffer::<U4>::from_data(vec![0u8; 6])
+            .dims([2, 3])
+            .build()
+            .unwrap();
+        match buf.layout() {
+            MemoryLayout::Tiled(layout) => {
+                assert_eq!(layout.minor_to_major, [1, 0]);
+                assert_eq!(layout.element_size_bits, Some(4));
+            }
+            layout => panic!("unexpected {:?}", layout),
+        }
+    }
+}
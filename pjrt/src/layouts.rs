@@ -0,0 +1,121 @@
+use pjrt_sys::{
+    PJRT_Buffer_Type, PJRT_Error, PJRT_Extension_Base, PJRT_Extension_Type,
+    PJRT_Extension_Type_PJRT_Extension_Type_Layouts, PJRT_Layouts_Extension,
+    PJRT_Layouts_MemoryLayout, PJRT_Layouts_MemoryLayout_Destroy_Args,
+    PJRT_Layouts_MemoryLayout_Serialize_Args, PJRT_Layouts_PJRT_Buffer_MemoryLayout_Args,
+    PJRT_Layouts_PJRT_Client_GetDefaultLayout_Args,
+};
+
+use crate::extension::{sealed, ApiExtension};
+use crate::{utils, Api, Buffer, Client, Error, Memory, MemoryLayout, PrimitiveType, Result};
+
+pub struct LayoutsExtension {
+    api: Api,
+    ptr: *mut PJRT_Layouts_Extension,
+}
+
+impl sealed::Sealed for LayoutsExtension {}
+
+impl ApiExtension for LayoutsExtension {
+    const EXTENSION_TYPE: PJRT_Extension_Type = PJRT_Extension_Type_PJRT_Extension_Type_Layouts;
+
+    fn wrap(api: &Api, ptr: *mut PJRT_Extension_Base) -> Self {
+        assert!(!ptr.is_null());
+        Self {
+            api: api.clone(),
+            ptr: ptr as *mut PJRT_Layouts_Extension,
+        }
+    }
+}
+
+macro_rules! layouts_fn_ret_err {
+    ($fn:ident, $args_ty:ident) => {
+        impl LayoutsExtension {
+            #[allow(non_snake_case)]
+            fn $fn(&self, mut args: pjrt_sys::$args_ty) -> Result<pjrt_sys::$args_ty> {
+                let func = unsafe { (*self.ptr).$fn }
+                    .ok_or(Error::NullFunctionPointer(stringify!($fn)))?;
+                let err: *mut PJRT_Error = unsafe { func(&mut args as *mut _) };
+                self.api.err_or(err, args)
+            }
+        }
+    };
+}
+
+layouts_fn_ret_err!(
+    PJRT_Layouts_MemoryLayout_Destroy,
+    PJRT_Layouts_MemoryLayout_Destroy_Args
+);
+layouts_fn_ret_err!(
+    PJRT_Layouts_MemoryLayout_Serialize,
+    PJRT_Layouts_MemoryLayout_Serialize_Args
+);
+layouts_fn_ret_err!(
+    PJRT_Layouts_PJRT_Client_GetDefaultLayout,
+    PJRT_Layouts_PJRT_Client_GetDefaultLayout_Args
+);
+layouts_fn_ret_err!(
+    PJRT_Layouts_PJRT_Buffer_MemoryLayout,
+    PJRT_Layouts_PJRT_Buffer_MemoryLayout_Args
+);
+
+impl LayoutsExtension {
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    pub fn version(&self) -> usize {
+        unsafe { (*self.ptr).struct_size }
+    }
+
+    pub fn client_default_layout(
+        &self,
+        client: &Client,
+        ty: PrimitiveType,
+        dims: &[i64],
+    ) -> Result<MemoryLayout> {
+        let mut args = PJRT_Layouts_PJRT_Client_GetDefaultLayout_Args::new();
+        args.client = client.ptr();
+        args.type_ = ty as PJRT_Buffer_Type;
+        args.dims = dims.as_ptr();
+        args.num_dims = dims.len();
+        args = self.PJRT_Layouts_PJRT_Client_GetDefaultLayout(args)?;
+        self.take_layout(args.layout)
+    }
+
+    /// Default layout of a buffer of `ty` and `dims` in `memory`. Plugins
+    /// resolve default layouts per client, the memory's client is asked.
+    pub fn memory_default_layout(
+        &self,
+        memory: &Memory,
+        ty: PrimitiveType,
+        dims: &[i64],
+    ) -> Result<MemoryLayout> {
+        self.client_default_layout(memory.client(), ty, dims)
+    }
+
+    pub fn buffer_layout(&self, buffer: &Buffer) -> Result<MemoryLayout> {
+        let mut args = PJRT_Layouts_PJRT_Buffer_MemoryLayout_Args::new();
+        args.buffer = buffer.ptr;
+        args = self.PJRT_Layouts_PJRT_Buffer_MemoryLayout(args)?;
+        self.take_layout(args.layout)
+    }
+
+    // converts a layout returned by the plugin and frees it
+    fn take_layout(&self, layout: *mut PJRT_Layouts_MemoryLayout) -> Result<MemoryLayout> {
+        let mut args = PJRT_Layouts_MemoryLayout_Serialize_Args::new();
+        args.layout = layout;
+        let serialized = self.PJRT_Layouts_MemoryLayout_Serialize(args).map(|args| {
+            let s =
+                utils::str_from_raw(args.serialized_bytes, args.serialized_bytes_size).into_owned();
+            if let Some(deleter) = args.serialized_layout_deleter {
+                unsafe { deleter(args.serialized_layout) };
+            }
+            s
+        });
+        let mut args = PJRT_Layouts_MemoryLayout_Destroy_Args::new();
+        args.layout = layout;
+        self.PJRT_Layouts_MemoryLayout_Destroy(args)?;
+        serialized?.parse()
+    }
+}
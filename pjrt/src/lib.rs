@@ -14,20 +14,55 @@ pub use plugin::plugin;
 mod api;
 pub use api::Api;
 
+mod extension;
+pub use extension::{
+    ApiExtension, CustomPartitionerExtension, Extension, GpuCustomCallExtension, StreamExtension,
+};
+
+mod ffi;
+pub use ffi::{
+    FfiAttr, FfiAttrValue, FfiBuffer, FfiBufferMut, FfiCallFrame, FfiExtension, FfiHandler,
+};
+
+mod layouts;
+pub use layouts::LayoutsExtension;
+
+mod profiler;
+pub use profiler::{xspace_to_chrome_trace, Profiler, ProfilerExtension};
+
 mod client;
-pub use client::Client;
+pub use client::{Client, OnDeleteCallback};
 
 mod buffer;
-pub use buffer::Buffer;
+pub use buffer::{BorrowedBuffer, Buffer, ExternalBufferRef};
+
+#[cfg(feature = "ndarray")]
+mod array;
+
+mod dlpack;
+
+mod typed_buffer;
+pub use typed_buffer::{TypedBuffer, DYN_RANK};
 
 mod host_buffer;
-pub use host_buffer::{HostBuffer, TypedHostBuffer};
+pub use host_buffer::{HostBuffer, PackedHostBuffer, TypedHostBuffer};
+
+mod literal;
+pub use literal::Literal;
+
+mod shape;
+pub use shape::{Shape, ShapeTree};
 
 mod memory_layout;
 pub use memory_layout::MemoryLayout;
 
+mod xla_flags;
+
 mod compile;
-pub use compile::{CompileOptions, CompileToExecutable, CompileToLoadedExecutable};
+pub use compile::{
+    CompilationEnvironments, CompileOptions, CompileToExecutable, CompileToLoadedExecutable,
+    DebugOptions, ExecutableBuildOptions, OptionOverride,
+};
 
 mod device;
 pub use device::{Device, GlobalDeviceId, LocalDeviceId, LocalHardwareId, MemoryStats};
@@ -50,17 +85,26 @@ pub use program::{Program, ProgramFormat};
 mod loaded_executable;
 pub use loaded_executable::LoadedExecutable;
 
+mod compilation_cache;
+pub use compilation_cache::CompilationCache;
+
 mod executable;
 pub use executable::{CompiledMemoryStats, Executable};
 
+mod executable_bundle;
+pub use executable_bundle::ExecutableBundle;
+
 mod event;
-pub use event::Event;
+pub use event::{Event, JoinAll};
 
 mod named_value;
 pub use named_value::{NamedValue, NamedValueMap};
 
 mod execute;
-pub use execute::{ExecuteContext, ExecuteOptions, Execution, ExecutionInputs};
+pub use execute::{
+    Donate, ExecuteContext, ExecuteOptions, Execution, ExecutionInput, ExecutionInputs,
+    RecvCallback, SendCallback,
+};
 
 mod device_stream;
 pub use device_stream::CopyToDeviceStream;
@@ -72,3 +116,4 @@ mod kv_store;
 pub use kv_store::KeyValueStore;
 // re-export pjrt-sys
 pub use pjrt_sys::protos;
+pub use pjrt_sys::DLManagedTensor;
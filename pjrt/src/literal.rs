@@ -0,0 +1,549 @@
+use pjrt_sys::protos::xla::{self, LiteralProto, ShapeProto};
+
+use crate::{
+    f8e4m3b11fnuz, f8e4m3fn, f8e4m3fnuz, f8e5m2, f8e5m2fnuz, Error, HostBuffer, MemoryLayout,
+    PackedHostBuffer, PackedType, PrimitiveType, Result, Type, TypedHostBuffer, I2, I4, U2, U4,
+};
+
+/// A host value exchanged as an XLA literal, either an array or a tuple.
+#[derive(Debug)]
+pub enum Literal {
+    Array(HostBuffer),
+    Tuple(Vec<Literal>),
+}
+
+impl Literal {
+    pub fn to_literal_proto(&self) -> Result<LiteralProto> {
+        match self {
+            Literal::Array(buf) => buf.to_literal_proto(),
+            Literal::Tuple(elements) => {
+                let tuple_literals = elements
+                    .iter()
+                    .map(|e| e.to_literal_proto())
+                    .collect::<Result<Vec<_>>>()?;
+                let shape = ShapeProto {
+                    element_type: xla::PrimitiveType::Tuple as i32,
+                    tuple_shapes: tuple_literals
+                        .iter()
+                        .map(|l| l.shape.clone().unwrap_or_default())
+                        .collect(),
+                    ..Default::default()
+                };
+                Ok(LiteralProto {
+                    shape: Some(shape),
+                    tuple_literals,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+impl From<HostBuffer> for Literal {
+    fn from(buf: HostBuffer) -> Self {
+        Literal::Array(buf)
+    }
+}
+
+impl TryFrom<LiteralProto> for Literal {
+    type Error = Error;
+
+    fn try_from(literal: LiteralProto) -> Result<Self> {
+        let is_tuple = literal
+            .shape
+            .as_ref()
+            .is_some_and(|s| s.element_type == xla::PrimitiveType::Tuple as i32);
+        if is_tuple {
+            let elements = literal
+                .tuple_literals
+                .into_iter()
+                .map(Literal::try_from)
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Literal::Tuple(elements))
+        } else {
+            Ok(Literal::Array(HostBuffer::try_from(literal)?))
+        }
+    }
+}
+
+fn le_bytes<const N: usize, E>(bytes: &[u8], f: impl Fn([u8; N]) -> E) -> Result<Vec<E>> {
+    if !bytes.len().is_multiple_of(N) {
+        return Err(Error::InvalidLiteral(format!(
+            "{} bytes is not a multiple of the element size {}",
+            bytes.len(),
+            N
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|b| f(b.try_into().unwrap()))
+        .collect())
+}
+
+fn complex<F: Copy>(values: &[F]) -> Result<Vec<num_complex::Complex<F>>> {
+    if !values.len().is_multiple_of(2) {
+        return Err(Error::InvalidLiteral(
+            "complex values must be (real, imag) pairs".to_string(),
+        ));
+    }
+    Ok(values
+        .chunks_exact(2)
+        .map(|c| num_complex::Complex::new(c[0], c[1]))
+        .collect())
+}
+
+fn typed<T: Type>(
+    data: Vec<T::ElemType>,
+    dims: Vec<i64>,
+    layout: Option<MemoryLayout>,
+) -> Result<TypedHostBuffer<T>> {
+    if dims.iter().product::<i64>() != data.len() as i64 {
+        return Err(Error::ElementCountMismatch {
+            dims,
+            len: data.len(),
+        });
+    }
+    Ok(TypedHostBuffer::from_data(data)
+        .dims(dims)
+        .maybe_layout(layout)
+        .build())
+}
+
+// literals hold sub byte integers one per byte
+fn packed<T: PackedType>(
+    bytes: &[u8],
+    dims: Vec<i64>,
+    layout: Option<MemoryLayout>,
+) -> Result<PackedHostBuffer<T>> {
+    if dims.iter().product::<i64>() != bytes.len() as i64 {
+        return Err(Error::ElementCountMismatch {
+            dims,
+            len: bytes.len(),
+        });
+    }
+    let data = bytes.iter().map(|b| T::unpack(*b)).collect::<Vec<_>>();
+    PackedHostBuffer::from_data(data)
+        .dims(dims)
+        .maybe_layout(layout)
+        .build()
+}
+
+impl TryFrom<LiteralProto> for HostBuffer {
+    type Error = Error;
+
+    fn try_from(literal: LiteralProto) -> Result<Self> {
+        let shape = literal
+            .shape
+            .ok_or_else(|| Error::InvalidLiteral("missing shape".to_string()))?;
+        let ty = xla::PrimitiveType::try_from(shape.element_type)
+            .map_err(|_| Error::InvalidPrimitiveType(shape.element_type))?;
+        if ty == xla::PrimitiveType::Tuple {
+            return Err(Error::InvalidLiteral(
+                "tuple literals can only be read as a Literal".to_string(),
+            ));
+        }
+        let ty = PrimitiveType::try_from(ty)?;
+        let dims = shape.dimensions;
+        // without a layout, literals are major to minor like the host buffer default
+        let layout = match shape.layout {
+            Some(l) if l.minor_to_major.len() != dims.len() => {
+                return Err(Error::InvalidLiteral(format!(
+                    "layout minor_to_major {:?} doesn't match dims {:?}",
+                    l.minor_to_major, dims
+                )))
+            }
+            l => l.map(|l| MemoryLayout::from(l.as_ref())),
+        };
+        match ty {
+            PrimitiveType::Pred => Ok(Self::Bool(typed(literal.preds, dims, layout)?)),
+            PrimitiveType::S8 => Ok(Self::I8(typed(
+                literal.s8s.into_iter().map(|b| b as i8).collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::S16 => Ok(Self::I16(typed(
+                le_bytes(&literal.s16s, i16::from_le_bytes)?,
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::S32 => Ok(Self::I32(typed(literal.s32s, dims, layout)?)),
+            PrimitiveType::S64 => Ok(Self::I64(typed(literal.s64s, dims, layout)?)),
+            PrimitiveType::U8 => Ok(Self::U8(typed(literal.u8s, dims, layout)?)),
+            PrimitiveType::U16 => Ok(Self::U16(typed(
+                le_bytes(&literal.u16s, u16::from_le_bytes)?,
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::U32 => Ok(Self::U32(typed(literal.u32s, dims, layout)?)),
+            PrimitiveType::U64 => Ok(Self::U64(typed(literal.u64s, dims, layout)?)),
+            PrimitiveType::F16 => Ok(Self::F16(typed(
+                le_bytes(&literal.f16s, half::f16::from_le_bytes)?,
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::F32 => Ok(Self::F32(typed(literal.f32s, dims, layout)?)),
+            PrimitiveType::F64 => Ok(Self::F64(typed(literal.f64s, dims, layout)?)),
+            PrimitiveType::BF16 => Ok(Self::BF16(typed(
+                le_bytes(&literal.bf16s, half::bf16::from_le_bytes)?,
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::C64 => Ok(Self::C64(typed(complex(&literal.c64s)?, dims, layout)?)),
+            PrimitiveType::C128 => Ok(Self::C128(typed(complex(&literal.c128s)?, dims, layout)?)),
+            PrimitiveType::F8E5M2 => Ok(Self::F8E5M2(typed(
+                literal.f8e5m2s.into_iter().map(f8e5m2::from_bits).collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::F8E4M3FN => Ok(Self::F8E4M3FN(typed(
+                literal
+                    .f8e4m3fns
+                    .into_iter()
+                    .map(f8e4m3fn::from_bits)
+                    .collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::F8E4M3B11FNUZ => Ok(Self::F8E4M3B11FNUZ(typed(
+                literal
+                    .f8e4m3b11fnuzs
+                    .into_iter()
+                    .map(f8e4m3b11fnuz::from_bits)
+                    .collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::F8E5M2FNUZ => Ok(Self::F8E5M2FNUZ(typed(
+                literal
+                    .f8e5m2fnuzs
+                    .into_iter()
+                    .map(f8e5m2fnuz::from_bits)
+                    .collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::F8E4M3FNUZ => Ok(Self::F8E4M3FNUZ(typed(
+                literal
+                    .f8e4m3fnuzs
+                    .into_iter()
+                    .map(f8e4m3fnuz::from_bits)
+                    .collect(),
+                dims,
+                layout,
+            )?)),
+            PrimitiveType::S4 => Ok(Self::I4(packed::<I4>(&literal.s4s, dims, layout)?)),
+            PrimitiveType::U4 => Ok(Self::U4(packed::<U4>(&literal.u4s, dims, layout)?)),
+            PrimitiveType::S2 => Ok(Self::I2(packed::<I2>(&literal.s2s, dims, layout)?)),
+            PrimitiveType::U2 => Ok(Self::U2(packed::<U2>(&literal.u2s, dims, layout)?)),
+            _ => Err(Error::NotSupportedType(ty)),
+        }
+    }
+}
+
+impl HostBuffer {
+    /// Encodes the buffer as an XLA literal, keeping its layout.
+    pub fn to_literal_proto(&self) -> Result<LiteralProto> {
+        let ty = self.primitive_type();
+        let dims = self.dims();
+        let mut literal = LiteralProto::default();
+        let elem_size = match self {
+            Self::BF16(buf) => {
+                literal.bf16s = buf.data().iter().flat_map(|v| v.to_le_bytes()).collect();
+                2
+            }
+            Self::F16(buf) => {
+                literal.f16s = buf.data().iter().flat_map(|v| v.to_le_bytes()).collect();
+                2
+            }
+            Self::F32(buf) => {
+                literal.f32s = buf.data().to_vec();
+                4
+            }
+            Self::F64(buf) => {
+                literal.f64s = buf.data().to_vec();
+                8
+            }
+            Self::I8(buf) => {
+                literal.s8s = buf.data().iter().map(|v| *v as u8).collect();
+                1
+            }
+            Self::I16(buf) => {
+                literal.s16s = buf.data().iter().flat_map(|v| v.to_le_bytes()).collect();
+                2
+            }
+            Self::I32(buf) => {
+                literal.s32s = buf.data().to_vec();
+                4
+            }
+            Self::I64(buf) => {
+                literal.s64s = buf.data().to_vec();
+                8
+            }
+            Self::U8(buf) => {
+                literal.u8s = buf.data().to_vec();
+                1
+            }
+            Self::U16(buf) => {
+                literal.u16s = buf.data().iter().flat_map(|v| v.to_le_bytes()).collect();
+                2
+            }
+            Self::U32(buf) => {
+                literal.u32s = buf.data().to_vec();
+                4
+            }
+            Self::U64(buf) => {
+                literal.u64s = buf.data().to_vec();
+                8
+            }
+            Self::C64(buf) => {
+                literal.c64s = buf.data().iter().flat_map(|c| [c.re, c.im]).collect();
+                8
+            }
+            Self::C128(buf) => {
+                literal.c128s = buf.data().iter().flat_map(|c| [c.re, c.im]).collect();
+                16
+            }
+            Self::Bool(buf) => {
+                literal.preds = buf.data().to_vec();
+                1
+            }
+            Self::F8E5M2(buf) => {
+                literal.f8e5m2s = buf.data().iter().map(|v| v.to_bits()).collect();
+                1
+            }
+            Self::F8E4M3FN(buf) => {
+                literal.f8e4m3fns = buf.data().iter().map(|v| v.to_bits()).collect();
+                1
+            }
+            Self::F8E4M3B11FNUZ(buf) => {
+                literal.f8e4m3b11fnuzs = buf.data().iter().map(|v| v.to_bits()).collect();
+                1
+            }
+            Self::F8E5M2FNUZ(buf) => {
+                literal.f8e5m2fnuzs = buf.data().iter().map(|v| v.to_bits()).collect();
+                1
+            }
+            Self::F8E4M3FNUZ(buf) => {
+                literal.f8e4m3fnuzs = buf.data().iter().map(|v| v.to_bits()).collect();
+                1
+            }
+            // packed layouts count elements, not bytes
+            Self::I4(buf) => {
+                literal.s4s = buf.values().into_iter().map(|v| v as u8).collect();
+                1
+            }
+            Self::U4(buf) => {
+                literal.u4s = buf.values();
+                1
+            }
+            Self::I2(buf) => {
+                literal.s2s = buf.values().into_iter().map(|v| v as u8).collect();
+                1
+            }
+            Self::U2(buf) => {
+                literal.u2s = buf.values();
+                1
+            }
+        };
+        literal.shape = Some(ShapeProto {
+            element_type: xla::PrimitiveType::from(ty) as i32,
+            dimensions: dims.to_vec(),
+            layout: Some(Box::new(self.layout().to_proto(dims, elem_size)?)),
+            ..Default::default()
+        });
+        Ok(literal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+
+    use super::*;
+
+    fn packed_buf<T: PackedType>(data: Vec<T::ElemType>) -> HostBuffer
+    where
+        HostBuffer: From<PackedHostBuffer<T>>,
+    {
+        PackedHostBuffer::<T>::from_data(data)
+            .dims([2, 2])
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    // one buffer of every type, 2x2
+    fn buffers() -> Vec<HostBuffer> {
+        vec![
+            HostBuffer::from_data(vec![true, false, false, true])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![-1i8, 2, -3, 4])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![-1i16, 2, -300, 4])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![-1i32, 2, -3, 70000])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![-1i64, 2, -3, 1 << 40])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1u8, 2, 3, 255])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1u16, 2, 3, 65535])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1u32, 2, 3, u32::MAX])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1u64, 2, 3, u64::MAX])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data([1.0, -2.5, 0.5, 8.0].map(half::f16::from_f32).to_vec())
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1.0f32, -2.5, 0.5, 8.0])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![1.0f64, -2.5, 0.5, 8.0])
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data([1.0, -2.5, 0.5, 8.0].map(half::bf16::from_f32).to_vec())
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(vec![
+                Complex::new(1.0f32, -1.0),
+                Complex::new(0.0, 2.0),
+                Complex::new(3.0, 0.0),
+                Complex::new(-4.0, 4.5),
+            ])
+            .dims([2, 2])
+            .build(),
+            HostBuffer::from_data(vec![
+                Complex::new(1.0f64, -1.0),
+                Complex::new(0.0, 2.0),
+                Complex::new(3.0, 0.0),
+                Complex::new(-4.0, 4.5),
+            ])
+            .dims([2, 2])
+            .build(),
+            HostBuffer::from_data([0x3c, 0xc0, 0x00, 0x7b].map(f8e5m2::from_bits).to_vec())
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data([0x38, 0xb8, 0x00, 0x7e].map(f8e4m3fn::from_bits).to_vec())
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data(
+                [0x58, 0xd8, 0x00, 0x7f]
+                    .map(f8e4m3b11fnuz::from_bits)
+                    .to_vec(),
+            )
+            .dims([2, 2])
+            .build(),
+            HostBuffer::from_data([0x40, 0xc0, 0x00, 0x7f].map(f8e5m2fnuz::from_bits).to_vec())
+                .dims([2, 2])
+                .build(),
+            HostBuffer::from_data([0x40, 0xc0, 0x00, 0x7f].map(f8e4m3fnuz::from_bits).to_vec())
+                .dims([2, 2])
+                .build(),
+            packed_buf::<I4>(vec![-8, 7, -1, 0]),
+            packed_buf::<U4>(vec![0, 15, 1, 8]),
+            packed_buf::<I2>(vec![-2, 1, -1, 0]),
+            packed_buf::<U2>(vec![0, 3, 1, 2]),
+        ]
+    }
+
+    #[test]
+    fn round_trip_every_type() {
+        for buf in buffers() {
+            let ty = buf.primitive_type();
+            let literal = buf.to_literal_proto().unwrap();
+            let shape = literal.shape.as_ref().unwrap();
+            assert_eq!(shape.element_type, xla::PrimitiveType::from(ty) as i32);
+            assert_eq!(shape.dimensions, [2, 2]);
+            let decoded = HostBuffer::try_from(literal.clone()).unwrap();
+            assert_eq!(decoded.primitive_type(), ty, "{:?}", ty);
+            assert_eq!(decoded.dims(), [2, 2]);
+            assert_eq!(decoded.to_literal_proto().unwrap(), literal, "{:?}", ty);
+        }
+    }
+
+    #[test]
+    fn round_trip_values() {
+        let buf = HostBuffer::from_data(vec![-1i16, 2, -300, 4])
+            .dims([2, 2])
+            .build();
+        match HostBuffer::try_from(buf.to_literal_proto().unwrap()).unwrap() {
+            HostBuffer::I16(buf) => assert_eq!(buf.data(), [-1, 2, -300, 4]),
+            buf => panic!("unexpected {:?}", buf),
+        }
+        match HostBuffer::try_from(
+            packed_buf::<I4>(vec![-8, 7, -1, 0])
+                .to_literal_proto()
+                .unwrap(),
+        )
+        .unwrap()
+        {
+            HostBuffer::I4(buf) => assert_eq!(buf.values(), [-8, 7, -1, 0]),
+            buf => panic!("unexpected {:?}", buf),
+        }
+    }
+
+    #[test]
+    fn round_trip_tuple() {
+        let literal = Literal::Tuple(vec![
+            Literal::Array(HostBuffer::from_scalar(1.5f32)),
+            Literal::Tuple(vec![Literal::Array(
+                HostBuffer::from_data(vec![1i32, 2]).build(),
+            )]),
+        ]);
+        let proto = literal.to_literal_proto().unwrap();
+        let shape = proto.shape.as_ref().unwrap();
+        assert_eq!(shape.element_type, xla::PrimitiveType::Tuple as i32);
+        assert_eq!(shape.tuple_shapes.len(), 2);
+        let decoded = Literal::try_from(proto.clone()).unwrap();
+        assert_eq!(decoded.to_literal_proto().unwrap(), proto);
+        assert!(matches!(
+            HostBuffer::try_from(proto),
+            Err(Error::InvalidLiteral(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_literals() {
+        let buf = HostBuffer::from_data(vec![1.0f32, 2.0, 3.0, 4.0])
+            .dims([2, 2])
+            .build();
+        let literal = buf.to_literal_proto().unwrap();
+
+        let mut bad = literal.clone();
+        bad.shape
+            .as_mut()
+            .unwrap()
+            .layout
+            .as_mut()
+            .unwrap()
+            .minor_to_major = vec![0];
+        assert!(matches!(
+            HostBuffer::try_from(bad),
+            Err(Error::InvalidLiteral(_))
+        ));
+
+        let mut bad = literal.clone();
+        bad.f32s.pop();
+        assert!(matches!(
+            HostBuffer::try_from(bad),
+            Err(Error::ElementCountMismatch { .. })
+        ));
+
+        let mut bad = literal;
+        bad.shape = None;
+        assert!(matches!(
+            HostBuffer::try_from(bad),
+            Err(Error::InvalidLiteral(_))
+        ));
+    }
+}
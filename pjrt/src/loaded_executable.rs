@@ -1,5 +1,6 @@
 use std::mem::MaybeUninit;
 use std::slice;
+use std::sync::OnceLock;
 
 use bon::bon;
 use pjrt_sys::{
@@ -10,15 +11,21 @@ use pjrt_sys::{
 };
 
 use crate::{
-    event, utils, Buffer, Client, CompileOptions, CompileToLoadedExecutable, Device, Event,
-    Executable, ExecuteOptions, Execution, ExecutionInputs, Result,
+    event, utils, Buffer, Client, CompileOptions, CompileToLoadedExecutable, Device, Error, Event,
+    Executable, ExecuteOptions, Execution, ExecutionInputs, Result, Shape, ShapeTree,
 };
 
 pub struct LoadedExecutable {
     client: Client,
     pub(crate) ptr: *mut PJRT_LoadedExecutable,
+    output_shape: OnceLock<Shape>,
 }
 
+// loaded executables are immutable once compiled, and PJRT allows concurrent
+// executions of the same executable.
+unsafe impl Send for LoadedExecutable {}
+unsafe impl Sync for LoadedExecutable {}
+
 impl Drop for LoadedExecutable {
     fn drop(&mut self) {
         let mut args = PJRT_LoadedExecutable_Destroy_Args::new();
@@ -37,6 +44,7 @@ impl LoadedExecutable {
         Self {
             client: client.clone(),
             ptr,
+            output_shape: OnceLock::new(),
         }
     }
 
@@ -57,32 +65,73 @@ impl LoadedExecutable {
     }
 
     pub fn executable(&self) -> Executable {
+        self.try_executable()
+            .expect("PJRT_LoadedExecutable_GetExecutable")
+    }
+
+    pub fn try_executable(&self) -> Result<Executable> {
         let mut args = PJRT_LoadedExecutable_GetExecutable_Args::new();
         args.loaded_executable = self.ptr;
         args = self
             .client
             .api()
-            .PJRT_LoadedExecutable_GetExecutable(args)
-            .expect("PJRT_LoadedExecutable_GetExecutable");
-        Executable::wrap(self.client.api(), args.executable)
+            .PJRT_LoadedExecutable_GetExecutable(args)?;
+        Ok(Executable::wrap(self.client.api(), args.executable))
+    }
+
+    /// Shape of the result executions are arranged as, see
+    /// [`Executable::output_shape`]. It falls back to
+    /// [`Executable::flat_output_shape`] when the plugin doesn't provide the
+    /// optimized program, or when its outputs aren't the leaves of the result,
+    /// e.g. with nested tuples counted as their top-level elements.
+    pub fn output_shape(&self) -> Result<&Shape> {
+        if let Some(shape) = self.output_shape.get() {
+            return Ok(shape);
+        }
+        let executable = self.try_executable()?;
+        let num_outputs = executable.try_num_outputs()?;
+        let shape = match executable.output_shape() {
+            Ok(shape) if shape.leaf_count() == num_outputs => shape,
+            _ => executable.flat_output_shape()?,
+        };
+        if shape.leaf_count() != num_outputs {
+            return Err(Error::LeafCountMismatch {
+                expected: shape.leaf_count(),
+                actual: num_outputs,
+            });
+        }
+        Ok(self.output_shape.get_or_init(|| shape))
+    }
+
+    // arranges the flat outputs of each device as the result shape
+    pub(crate) fn output_trees(&self, outputs: Vec<Vec<Buffer>>) -> Result<Vec<ShapeTree<Buffer>>> {
+        let shape = self.output_shape()?;
+        outputs
+            .into_iter()
+            .map(|buffers| ShapeTree::from_leaves(shape, buffers))
+            .collect()
     }
 
     pub fn addressable_devices(&self) -> Vec<Device> {
+        self.try_addressable_devices()
+            .expect("PJRT_LoadedExecutable_AddressableDevices")
+    }
+
+    pub fn try_addressable_devices(&self) -> Result<Vec<Device>> {
         let mut args = PJRT_LoadedExecutable_AddressableDevices_Args::new();
         args.executable = self.ptr;
         args = self
             .client
             .api()
-            .PJRT_LoadedExecutable_AddressableDevices(args)
-            .expect("PJRT_LoadedExecutable_AddressableDevices");
+            .PJRT_LoadedExecutable_AddressableDevices(args)?;
         let raw_devices = unsafe {
             slice::from_raw_parts(args.addressable_devices, args.num_addressable_devices)
         };
-        raw_devices
+        Ok(raw_devices
             .iter()
             .cloned()
             .map(|d| Device::wrap(&self.client, d))
-            .collect()
+            .collect())
     }
 
     pub fn delete(self) {
@@ -95,14 +144,15 @@ impl LoadedExecutable {
     }
 
     pub fn is_deleted(&self) -> bool {
+        self.try_is_deleted()
+            .expect("PJRT_LoadedExecutable_IsDeleted")
+    }
+
+    pub fn try_is_deleted(&self) -> Result<bool> {
         let mut args = PJRT_LoadedExecutable_IsDeleted_Args::new();
         args.executable = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_LoadedExecutable_IsDeleted(args)
-            .expect("PJRT_LoadedExecutable_IsDeleted");
-        args.is_deleted
+        args = self.client.api().PJRT_LoadedExecutable_IsDeleted(args)?;
+        Ok(args.is_deleted)
     }
 
     pub fn call_execute<I>(
@@ -113,11 +163,33 @@ impl LoadedExecutable {
     where
         I: ExecutionInputs,
     {
-        let executable = self.executable();
-        let num_outputs = executable.num_outputs();
+        let num_outputs = self.try_executable()?.try_num_outputs()?;
+        // outputs are arranged as the shape once the execution completes, find
+        // it before launching so that it can't fail after
+        self.output_shape()?;
         let input_buffers = inputs.buffer_ptrs();
+        // borrowed inputs are never donated, whatever the options say
+        let mut non_donatable = inputs.non_donatable_input_indices();
+        non_donatable.extend(&options.non_donatable_input_indices);
+        non_donatable.sort();
+        non_donatable.dedup();
         let mut args = PJRT_LoadedExecutable_Execute_Args::new();
         args.executable = self.ptr;
+        if let Some(device) = &options.execute_device {
+            if !self
+                .try_addressable_devices()?
+                .iter()
+                .any(|d| d.ptr == device.ptr)
+            {
+                return Err(Error::DeviceNotAddressable(
+                    device.try_description()?.try_id()?,
+                ));
+            }
+            if input_buffers.len() != 1 {
+                return Err(Error::InvalidArgumentListCount(input_buffers.len()));
+            }
+            args.execute_device = device.ptr;
+        }
         args.num_devices = input_buffers.len();
         args.num_args = input_buffers[0].len();
         // allocate argument lists pass to pjrt runtime
@@ -134,7 +206,11 @@ impl LoadedExecutable {
         let complete_events = vec![MaybeUninit::<*mut PJRT_Event>::uninit(); args.num_devices];
         args.device_complete_events = complete_events.as_ptr() as *mut *mut PJRT_Event;
         // options
-        let mut options = options.into();
+        let mut host_callbacks = options.host_callbacks(self.client.api(), args.num_devices)?;
+        let mut options: PJRT_ExecuteOptions = options.into();
+        options.non_donatable_input_indices = non_donatable.as_ptr();
+        options.num_non_donatable_input_indices = non_donatable.len();
+        host_callbacks.set_options(&mut options);
         args.options = &mut options as *mut PJRT_ExecuteOptions;
         args = self.client.api().PJRT_LoadedExecutable_Execute(args)?;
         let events =
@@ -144,6 +220,9 @@ impl LoadedExecutable {
             .cloned()
             .map(|ptr| event::Event::wrap(self.client.api(), ptr))
             .collect::<Vec<_>>();
+        for event in events.iter() {
+            host_callbacks.keep_alive(event);
+        }
         let output_buffers = unsafe {
             utils::slice_to_vec2d(args.output_lists, args.num_devices, num_outputs, |ptr| {
                 Buffer::wrap(&self.client, ptr)
@@ -152,26 +231,33 @@ impl LoadedExecutable {
         Ok((events, output_buffers))
     }
 
-    pub fn execute_sync<I>(&self, inputs: I, options: &ExecuteOptions) -> Result<Vec<Vec<Buffer>>>
+    /// Runs the executable and waits for it, returning the result of each
+    /// device.
+    pub fn execute_sync<I>(
+        &self,
+        inputs: I,
+        options: &ExecuteOptions,
+    ) -> Result<Vec<ShapeTree<Buffer>>>
     where
         I: ExecutionInputs,
     {
         let (events, outputs) = self.call_execute(inputs, options)?;
-        for event in events {
-            event.wait()?;
-        }
-        Ok(outputs)
+        Event::join_all(events).wait()?;
+        self.output_trees(outputs)
     }
 
-    pub async fn execute<I>(&self, inputs: I, options: &ExecuteOptions) -> Result<Vec<Vec<Buffer>>>
+    /// Runs the executable, returning the result of each device.
+    pub async fn execute<I>(
+        &self,
+        inputs: I,
+        options: &ExecuteOptions,
+    ) -> Result<Vec<ShapeTree<Buffer>>>
     where
         I: ExecutionInputs,
     {
         let (events, outputs) = self.call_execute(inputs, options)?;
-        for event in events {
-            event.await?;
-        }
-        Ok(outputs)
+        Event::join_all(events).await?;
+        self.output_trees(outputs)
     }
 
     pub fn execution<I>(&self, inputs: I) -> Execution<I>
@@ -8,13 +8,17 @@ use pjrt_sys::{
     PJRT_Memory_ToString_Args,
 };
 
-use crate::{utils, Client, Device};
+use crate::{utils, Client, Device, Result};
 
 pub struct Memory {
     client: Client,
     pub(crate) ptr: *mut PJRT_Memory,
 }
 
+// memories are owned by the client and never change after it is created.
+unsafe impl Send for Memory {}
+unsafe impl Sync for Memory {}
+
 impl Memory {
     pub(crate) fn wrap(client: &Client, ptr: *mut PJRT_Memory) -> Memory {
         assert!(!ptr.is_null());
@@ -29,73 +33,77 @@ impl Memory {
     }
 
     pub fn id(&self) -> i32 {
+        self.try_id().expect("PJRT_Memory_Id")
+    }
+
+    pub fn try_id(&self) -> Result<i32> {
         let mut args = PJRT_Memory_Id_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_Id(args)
-            .expect("PJRT_Memory_Id");
-        args.id
+        args = self.client.api().PJRT_Memory_Id(args)?;
+        Ok(args.id)
     }
 
     pub fn kind(&self) -> Cow<'_, str> {
+        self.try_kind().expect("PJRT_Memory_Kind")
+    }
+
+    pub fn try_kind(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Memory_Kind_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_Kind(args)
-            .expect("PJRT_Memory_Kind");
-        utils::str_from_raw(args.kind, args.kind_size)
+        args = self.client.api().PJRT_Memory_Kind(args)?;
+        Ok(utils::str_from_raw(args.kind, args.kind_size))
     }
 
     pub fn kind_id(&self) -> i32 {
+        self.try_kind_id().expect("PJRT_Memory_Kind_Id")
+    }
+
+    pub fn try_kind_id(&self) -> Result<i32> {
         let mut args = PJRT_Memory_Kind_Id_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_Kind_Id(args)
-            .expect("PJRT_Memory_Kind_Id");
-        args.kind_id
+        args = self.client.api().PJRT_Memory_Kind_Id(args)?;
+        Ok(args.kind_id)
     }
 
     pub fn debug_string(&self) -> Cow<'_, str> {
+        self.try_debug_string().expect("PJRT_Memory_DebugString")
+    }
+
+    pub fn try_debug_string(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Memory_DebugString_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_DebugString(args)
-            .expect("PJRT_Memory_DebugString");
-        utils::str_from_raw(args.debug_string, args.debug_string_size)
+        args = self.client.api().PJRT_Memory_DebugString(args)?;
+        Ok(utils::str_from_raw(
+            args.debug_string,
+            args.debug_string_size,
+        ))
     }
 
     pub fn to_string(&self) -> Cow<'_, str> {
+        self.try_to_string().expect("PJRT_Memory_ToString")
+    }
+
+    pub fn try_to_string(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_Memory_ToString_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_ToString(args)
-            .expect("PJRT_Memory_ToString");
-        utils::str_from_raw(args.to_string, args.to_string_size)
+        args = self.client.api().PJRT_Memory_ToString(args)?;
+        Ok(utils::str_from_raw(args.to_string, args.to_string_size))
     }
 
     pub fn addressable_by_devices(&self) -> Vec<Device> {
+        self.try_addressable_by_devices()
+            .expect("PJRT_Memory_AddressableByDevices")
+    }
+
+    pub fn try_addressable_by_devices(&self) -> Result<Vec<Device>> {
         let mut args = PJRT_Memory_AddressableByDevices_Args::new();
         args.memory = self.ptr;
-        args = self
-            .client
-            .api()
-            .PJRT_Memory_AddressableByDevices(args)
-            .expect("PJRT_Memory_AddressableByDevices");
+        args = self.client.api().PJRT_Memory_AddressableByDevices(args)?;
         let devices = unsafe { slice::from_raw_parts(args.devices, args.num_devices) };
-        devices
+        Ok(devices
             .iter()
             .map(|device| Device::wrap(&self.client, *device))
-            .collect()
+            .collect())
     }
 }
 
@@ -1,6 +1,8 @@
+use std::str::FromStr;
 use std::vec;
 
 use bon::bon;
+use pjrt_sys::protos::xla::{LayoutProto, TileProto};
 use pjrt_sys::{
     PJRT_Buffer_MemoryLayout, PJRT_Buffer_MemoryLayout_Type,
     PJRT_Buffer_MemoryLayout_Type_PJRT_Buffer_MemoryLayout_Type_Strides,
@@ -22,11 +24,15 @@ impl MemoryLayout {
         #[builder(start_fn, into)] minor_to_major: Vec<i64>,
         #[builder] tile_dims: Option<Vec<i64>>,
         #[builder] tile_dim_sizes: Option<Vec<usize>>,
+        /// Size of the elements for types narrower than a byte, packed.
+        #[builder]
+        element_size_bits: Option<i64>,
     ) -> MemoryLayout {
         MemoryLayout::Tiled(MemoryLayoutTiled {
             minor_to_major,
             tile_dims,
             tile_dim_sizes,
+            element_size_bits,
         })
     }
 
@@ -35,6 +41,197 @@ impl MemoryLayout {
             byte_strides: byte_strides.into(),
         })
     }
+
+    /// The dense layout of `dims` with the given strides in elements, which
+    /// must be a permutation of the dims without gaps.
+    pub fn from_element_strides(dims: &[i64], strides: &[i64]) -> Result<MemoryLayoutTiled> {
+        if dims.len() != strides.len() {
+            return Err(Error::InvalidMemoryLayout(format!(
+                "strides {:?} don't match dims {:?}",
+                strides, dims
+            )));
+        }
+        // size 1 dims can have any stride, keep them most minor
+        let mut minor_to_major = (0..dims.len()).collect::<Vec<_>>();
+        minor_to_major.sort_by_key(|d| (strides[*d], dims[*d] != 1));
+        let mut stride = 1;
+        for d in minor_to_major.iter() {
+            if dims[*d] != 1 && strides[*d] != stride {
+                return Err(Error::InvalidMemoryLayout(format!(
+                    "strides {:?} of dims {:?} are not dense",
+                    strides, dims
+                )));
+            }
+            stride *= dims[*d];
+        }
+        Ok(MemoryLayoutTiled {
+            minor_to_major: minor_to_major.into_iter().map(|d| d as i64).collect(),
+            tile_dims: None,
+            tile_dim_sizes: None,
+            element_size_bits: None,
+        })
+    }
+
+    /// Strides of `dims` in elements of `elem_size` bytes. Tiled layouts with
+    /// tiles have none.
+    pub fn element_strides(&self, dims: &[i64], elem_size: usize) -> Result<Vec<i64>> {
+        match self {
+            MemoryLayout::Strides(layout) => layout
+                .byte_strides
+                .iter()
+                .map(|stride| {
+                    if *stride % elem_size as i64 != 0 {
+                        return Err(Error::InvalidMemoryLayout(format!(
+                            "byte stride {} is not a multiple of the element size {}",
+                            stride, elem_size
+                        )));
+                    }
+                    Ok(*stride / elem_size as i64)
+                })
+                .collect(),
+            MemoryLayout::Tiled(layout) => {
+                if layout.tile_dims.as_ref().is_some_and(|t| !t.is_empty()) {
+                    return Err(Error::InvalidMemoryLayout(
+                        "tiled layouts have no strides".to_string(),
+                    ));
+                }
+                let mut strides = vec![0; dims.len()];
+                let mut stride = 1;
+                for d in layout.minor_to_major.iter() {
+                    let d = *d as usize;
+                    if d >= dims.len() {
+                        return Err(Error::InvalidMemoryLayout(format!(
+                            "minor_to_major {:?} doesn't match dims {:?}",
+                            layout.minor_to_major, dims
+                        )));
+                    }
+                    strides[d] = stride;
+                    stride *= dims[d];
+                }
+                Ok(strides)
+            }
+        }
+    }
+
+    /// The layout of `dims` as a proto, dense strides become a minor to major
+    /// order.
+    pub(crate) fn to_proto(&self, dims: &[i64], elem_size: usize) -> Result<LayoutProto> {
+        match self {
+            MemoryLayout::Tiled(layout) => Ok(LayoutProto::from(layout)),
+            MemoryLayout::Strides(_) => {
+                let strides = self.element_strides(dims, elem_size)?;
+                let layout = MemoryLayout::from_element_strides(dims, &strides)?;
+                Ok(LayoutProto::from(&layout))
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a LayoutProto> for MemoryLayout {
+    fn from(layout: &'a LayoutProto) -> Self {
+        let (tile_dims, tile_dim_sizes) = if layout.tiles.is_empty() {
+            (None, None)
+        } else {
+            let tile_dims = layout
+                .tiles
+                .iter()
+                .flat_map(|t| t.dimensions.iter().cloned())
+                .collect();
+            let tile_dim_sizes = layout.tiles.iter().map(|t| t.dimensions.len()).collect();
+            (Some(tile_dims), Some(tile_dim_sizes))
+        };
+        MemoryLayout::Tiled(MemoryLayoutTiled {
+            minor_to_major: layout.minor_to_major.clone(),
+            tile_dims,
+            tile_dim_sizes,
+            element_size_bits: (layout.element_size_in_bits != 0)
+                .then_some(layout.element_size_in_bits),
+        })
+    }
+}
+
+impl<'a> From<&'a MemoryLayoutTiled> for LayoutProto {
+    fn from(layout: &'a MemoryLayoutTiled) -> Self {
+        let mut tiles = Vec::new();
+        if let (Some(tile_dims), Some(tile_dim_sizes)) = (&layout.tile_dims, &layout.tile_dim_sizes)
+        {
+            let mut offset = 0;
+            for size in tile_dim_sizes.iter() {
+                tiles.push(TileProto {
+                    dimensions: tile_dims[offset..offset + size].to_vec(),
+                });
+                offset += size;
+            }
+        }
+        LayoutProto {
+            minor_to_major: layout.minor_to_major.clone(),
+            tiles,
+            element_size_in_bits: layout.element_size_bits.unwrap_or(0),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses the XLA layout string form, e.g. `{1,0:T(8,128)(2,1)}`.
+///
+/// Only the minor to major order, the tiles and the element size, e.g.
+/// `E(4)`, are kept, other attributes such as the memory space are ignored.
+impl FromStr for MemoryLayout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidMemoryLayout(s.to_string());
+        let inner = s
+            .trim()
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(invalid)?;
+        let (dims, attrs) = inner.split_once(':').unwrap_or((inner, ""));
+        let minor_to_major = dims
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(|d| d.parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        let mut tile_dims = vec![];
+        let mut tile_dim_sizes = vec![];
+        if let Some(tiles) = attrs.find('T').map(|i| &attrs[i + 1..]) {
+            let mut rest = tiles;
+            while let Some(tile) = rest.strip_prefix('(') {
+                let (tile, next) = tile.split_once(')').ok_or_else(invalid)?;
+                let dims = tile
+                    .split(',')
+                    .map(str::trim)
+                    .map(|d| match d {
+                        // the combined dimension marker
+                        "*" => Ok(i64::MIN),
+                        d => d.parse::<i64>().map_err(|_| invalid()),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                tile_dim_sizes.push(dims.len());
+                tile_dims.extend(dims);
+                rest = next;
+            }
+        }
+        let (tile_dims, tile_dim_sizes) = if tile_dim_sizes.is_empty() {
+            (None, None)
+        } else {
+            (Some(tile_dims), Some(tile_dim_sizes))
+        };
+        let element_size_bits = match attrs.find("E(").map(|i| &attrs[i + 2..]) {
+            Some(bits) => {
+                let (bits, _) = bits.split_once(')').ok_or_else(invalid)?;
+                Some(bits.trim().parse::<i64>().map_err(|_| invalid())?)
+            }
+            None => None,
+        };
+        Ok(MemoryLayout::Tiled(MemoryLayoutTiled {
+            minor_to_major,
+            tile_dims,
+            tile_dim_sizes,
+            element_size_bits,
+        }))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +239,8 @@ pub struct MemoryLayoutTiled {
     pub minor_to_major: Vec<i64>,
     pub tile_dims: Option<Vec<i64>>,
     pub tile_dim_sizes: Option<Vec<usize>>,
+    /// Not part of the PJRT C layout, only kept in protos.
+    pub element_size_bits: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -95,18 +294,22 @@ impl<'a> TryFrom<&'a PJRT_Buffer_MemoryLayout> for MemoryLayout {
                         minor_to_major,
                         tile_dims: None,
                         tile_dim_sizes: None,
+                        element_size_bits: None,
                     };
                     Ok(MemoryLayout::Tiled(layout))
                 } else {
-                    let tile_dims: &[i64] =
-                        unsafe { std::slice::from_raw_parts(tiled.tile_dims, tiled.num_tiles) };
                     let tile_dim_sizes = unsafe {
                         std::slice::from_raw_parts(tiled.tile_dim_sizes, tiled.num_tiles)
                     };
+                    // tile_dims is the concatenation of all tiles
+                    let tile_dims: &[i64] = unsafe {
+                        std::slice::from_raw_parts(tiled.tile_dims, tile_dim_sizes.iter().sum())
+                    };
                     let layout = MemoryLayoutTiled {
                         minor_to_major,
                         tile_dims: Some(tile_dims.to_vec()),
                         tile_dim_sizes: Some(tile_dim_sizes.to_vec()),
+                        element_size_bits: None,
                     };
                     Ok(MemoryLayout::Tiled(layout))
                 }
@@ -144,10 +347,10 @@ impl<'a> From<&'a MemoryLayoutTiled> for PJRT_Buffer_MemoryLayout {
         pjrt_layout.__bindgen_anon_1.tiled.minor_to_major_size = layout.minor_to_major.len();
         if let Some(tile_dims) = &layout.tile_dims {
             pjrt_layout.__bindgen_anon_1.tiled.tile_dims = tile_dims.as_ptr();
-            pjrt_layout.__bindgen_anon_1.tiled.num_tiles = tile_dims.len();
         }
         if let Some(tile_dim_sizes) = &layout.tile_dim_sizes {
             pjrt_layout.__bindgen_anon_1.tiled.tile_dim_sizes = tile_dim_sizes.as_ptr();
+            pjrt_layout.__bindgen_anon_1.tiled.num_tiles = tile_dim_sizes.len();
         }
         pjrt_layout
     }
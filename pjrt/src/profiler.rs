@@ -0,0 +1,438 @@
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::slice;
+
+use pjrt_sys::protos::tensorflow::profiler::{x_event, x_stat, XPlane, XSpace, XStat};
+use pjrt_sys::protos::tensorflow::ProfileOptions;
+use pjrt_sys::{
+    PJRT_Error_Code, PJRT_Extension_Base, PJRT_Extension_Type,
+    PJRT_Extension_Type_PJRT_Extension_Type_Profiler, PJRT_Profiler_Extension, PLUGIN_Profiler,
+    PLUGIN_Profiler_Api, PLUGIN_Profiler_CollectData_Args, PLUGIN_Profiler_Create_Args,
+    PLUGIN_Profiler_Destroy_Args, PLUGIN_Profiler_Error, PLUGIN_Profiler_Error_Destroy_Args,
+    PLUGIN_Profiler_Error_GetCode_Args, PLUGIN_Profiler_Error_Message_Args,
+    PLUGIN_Profiler_Start_Args, PLUGIN_Profiler_Stop_Args,
+};
+use prost::Message;
+
+use crate::error::ErrorCode;
+use crate::extension::{sealed, ApiExtension};
+use crate::{utils, Api, Error, Result};
+
+pub struct ProfilerExtension {
+    api: Api,
+    ptr: *mut PJRT_Profiler_Extension,
+}
+
+impl sealed::Sealed for ProfilerExtension {}
+
+impl ApiExtension for ProfilerExtension {
+    const EXTENSION_TYPE: PJRT_Extension_Type = PJRT_Extension_Type_PJRT_Extension_Type_Profiler;
+
+    fn wrap(api: &Api, ptr: *mut PJRT_Extension_Base) -> Self {
+        assert!(!ptr.is_null());
+        Self {
+            api: api.clone(),
+            ptr: ptr as *mut PJRT_Profiler_Extension,
+        }
+    }
+}
+
+impl ProfilerExtension {
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    pub fn version(&self) -> usize {
+        unsafe { (*self.ptr).struct_size }
+    }
+
+    fn profiler_api(&self) -> Result<&PLUGIN_Profiler_Api> {
+        let api = unsafe { (*self.ptr).profiler_api };
+        if api.is_null() {
+            return Err(Error::NullPointer);
+        }
+        Ok(unsafe { &*api })
+    }
+
+    fn err_or<T>(&self, err: *mut PLUGIN_Profiler_Error, value: T) -> Result<T> {
+        if err.is_null() {
+            return Ok(value);
+        }
+        let mut args = PLUGIN_Profiler_Error_Message_Args::new();
+        args.error = err;
+        self.PLUGIN_Profiler_Error_Message(&mut args)?;
+        let msg = utils::str_from_raw(args.message, args.message_size).into_owned();
+        let mut args = PLUGIN_Profiler_Error_GetCode_Args::new();
+        args.error = err;
+        let code = self
+            .PLUGIN_Profiler_Error_GetCode(&mut args)
+            .and_then(|_| ErrorCode::try_from(args.code as PJRT_Error_Code))
+            .unwrap_or(ErrorCode::Unknown);
+        let mut args = PLUGIN_Profiler_Error_Destroy_Args::new();
+        args.error = err;
+        self.PLUGIN_Profiler_Error_Destroy(&mut args)?;
+        let backtrace = Backtrace::capture().to_string();
+        Err(Error::PjrtError {
+            msg,
+            code,
+            backtrace,
+        })
+    }
+}
+
+macro_rules! profiler_fn_ret_err {
+    ($fn:ident, $args_ty:ident) => {
+        impl ProfilerExtension {
+            #[allow(non_snake_case)]
+            fn $fn(&self, mut args: $args_ty) -> Result<$args_ty> {
+                let func = self
+                    .profiler_api()?
+                    .$fn
+                    .ok_or(Error::NullFunctionPointer(stringify!($fn)))?;
+                let err = unsafe { func(&mut args as *mut _) };
+                self.err_or(err, args)
+            }
+        }
+    };
+}
+
+macro_rules! profiler_fn_ret_void {
+    ($fn:ident, $args_ty:ident) => {
+        impl ProfilerExtension {
+            #[allow(non_snake_case)]
+            fn $fn(&self, args: &mut $args_ty) -> Result<()> {
+                let func = self
+                    .profiler_api()?
+                    .$fn
+                    .ok_or(Error::NullFunctionPointer(stringify!($fn)))?;
+                unsafe { func(args as *mut _) };
+                Ok(())
+            }
+        }
+    };
+}
+
+profiler_fn_ret_void!(
+    PLUGIN_Profiler_Error_Destroy,
+    PLUGIN_Profiler_Error_Destroy_Args
+);
+profiler_fn_ret_void!(
+    PLUGIN_Profiler_Error_Message,
+    PLUGIN_Profiler_Error_Message_Args
+);
+
+impl ProfilerExtension {
+    // unlike the other functions, a failure here must not be reported through
+    // `err_or` again
+    #[allow(non_snake_case)]
+    fn PLUGIN_Profiler_Error_GetCode(
+        &self,
+        args: &mut PLUGIN_Profiler_Error_GetCode_Args,
+    ) -> Result<()> {
+        let func = self
+            .profiler_api()?
+            .PLUGIN_Profiler_Error_GetCode
+            .ok_or(Error::NullFunctionPointer("PLUGIN_Profiler_Error_GetCode"))?;
+        let err = unsafe { func(args as *mut _) };
+        if !err.is_null() {
+            let mut destroy_args = PLUGIN_Profiler_Error_Destroy_Args::new();
+            destroy_args.error = err;
+            self.PLUGIN_Profiler_Error_Destroy(&mut destroy_args)?;
+            return Err(Error::InvalidErrorCode(args.code));
+        }
+        Ok(())
+    }
+}
+
+profiler_fn_ret_err!(PLUGIN_Profiler_Create, PLUGIN_Profiler_Create_Args);
+profiler_fn_ret_err!(PLUGIN_Profiler_Destroy, PLUGIN_Profiler_Destroy_Args);
+profiler_fn_ret_err!(PLUGIN_Profiler_Start, PLUGIN_Profiler_Start_Args);
+profiler_fn_ret_err!(PLUGIN_Profiler_Stop, PLUGIN_Profiler_Stop_Args);
+profiler_fn_ret_err!(
+    PLUGIN_Profiler_CollectData,
+    PLUGIN_Profiler_CollectData_Args
+);
+
+/// A running profiling session of the plugin's device profiler.
+///
+/// The session is stopped and destroyed on drop if [`Profiler::stop`] was not
+/// called.
+pub struct Profiler {
+    ext: ProfilerExtension,
+    ptr: *mut PLUGIN_Profiler,
+    running: bool,
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        if self.running {
+            let mut args = PLUGIN_Profiler_Stop_Args::new();
+            args.profiler = self.ptr;
+            let _ = self.ext.PLUGIN_Profiler_Stop(args);
+        }
+        let mut args = PLUGIN_Profiler_Destroy_Args::new();
+        args.profiler = self.ptr;
+        // a failed destroy only leaks the session, don't panic in drop
+        let _ = self.ext.PLUGIN_Profiler_Destroy(args);
+    }
+}
+
+impl Profiler {
+    pub fn start(api: &Api, options: &ProfileOptions) -> Result<Self> {
+        let ext = api
+            .extension::<ProfilerExtension>()
+            .ok_or(Error::ExtensionNotFound("profiler"))?;
+        let options = options.encode_to_vec();
+        let mut args = PLUGIN_Profiler_Create_Args::new();
+        args.options = options.as_ptr() as *const _;
+        args.options_size = options.len();
+        args = ext.PLUGIN_Profiler_Create(args)?;
+        let mut profiler = Self {
+            ext,
+            ptr: args.profiler,
+            running: false,
+        };
+        let mut args = PLUGIN_Profiler_Start_Args::new();
+        args.profiler = profiler.ptr;
+        profiler.ext.PLUGIN_Profiler_Start(args)?;
+        profiler.running = true;
+        Ok(profiler)
+    }
+
+    /// Stops the session and returns the collected serialized `XSpace`.
+    pub fn stop(mut self) -> Result<Vec<u8>> {
+        let mut args = PLUGIN_Profiler_Stop_Args::new();
+        args.profiler = self.ptr;
+        self.running = false;
+        self.ext.PLUGIN_Profiler_Stop(args)?;
+        self.collect_data()
+    }
+
+    fn collect_data(&self) -> Result<Vec<u8>> {
+        let mut args = PLUGIN_Profiler_CollectData_Args::new();
+        args.profiler = self.ptr;
+        args = self.ext.PLUGIN_Profiler_CollectData(args)?;
+        if args.buffer_size_in_bytes == 0 {
+            return Ok(vec![]);
+        }
+        // the plugin either hands out a buffer it owns, or only reports the
+        // size and expects to be called again with a buffer to fill
+        if !args.buffer.is_null() {
+            let data = unsafe { slice::from_raw_parts(args.buffer, args.buffer_size_in_bytes) };
+            return Ok(data.to_vec());
+        }
+        let mut data = vec![0u8; args.buffer_size_in_bytes];
+        args.buffer = data.as_mut_ptr();
+        args = self.ext.PLUGIN_Profiler_CollectData(args)?;
+        data.truncate(args.buffer_size_in_bytes);
+        Ok(data)
+    }
+}
+
+/// Converts a serialized `XSpace` into Chrome trace event JSON, viewable in
+/// Perfetto or `chrome://tracing`.
+pub fn xspace_to_chrome_trace(xspace: &[u8]) -> Result<String> {
+    let xspace = XSpace::decode(xspace)?;
+    let mut events = vec![];
+    for (pid, plane) in xspace.planes.iter().enumerate() {
+        events.push(format!(
+            r#"{{"ph":"M","pid":{},"name":"process_name","args":{{"name":{}}}}}"#,
+            pid,
+            json_str(&plane.name)
+        ));
+        for line in plane.lines.iter() {
+            let line_name = if line.display_name.is_empty() {
+                &line.name
+            } else {
+                &line.display_name
+            };
+            events.push(format!(
+                r#"{{"ph":"M","pid":{},"tid":{},"name":"thread_name","args":{{"name":{}}}}}"#,
+                pid,
+                line.id,
+                json_str(line_name)
+            ));
+            for event in line.events.iter() {
+                // aggregated events have no position on the timeline
+                let Some(x_event::Data::OffsetPs(offset_ps)) = event.data else {
+                    continue;
+                };
+                let metadata = plane.event_metadata.get(&event.metadata_id);
+                let name = metadata
+                    .map(|m| {
+                        if m.display_name.is_empty() {
+                            m.name.as_str()
+                        } else {
+                            m.display_name.as_str()
+                        }
+                    })
+                    .unwrap_or_default();
+                let stats = metadata
+                    .map(|m| m.stats.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .chain(event.stats.iter());
+                let ts = line.timestamp_ns as f64 / 1e3 + offset_ps as f64 / 1e6;
+                let dur = event.duration_ps as f64 / 1e6;
+                events.push(format!(
+                    r#"{{"ph":"X","pid":{},"tid":{},"ts":{},"dur":{},"name":{},"args":{{{}}}}}"#,
+                    pid,
+                    line.id,
+                    ts,
+                    dur,
+                    json_str(name),
+                    stats_json(plane, stats)
+                ));
+            }
+        }
+    }
+    Ok(format!(
+        r#"{{"displayTimeUnit":"ns","traceEvents":[{}]}}"#,
+        events.join(",")
+    ))
+}
+
+fn stats_json<'a>(plane: &XPlane, stats: impl Iterator<Item = &'a XStat>) -> String {
+    let stat_name = |id: i64| {
+        plane
+            .stat_metadata
+            .get(&id)
+            .map(|m| m.name.as_str())
+            .unwrap_or_default()
+    };
+    let mut args = vec![];
+    for stat in stats {
+        let value = match &stat.value {
+            Some(x_stat::Value::DoubleValue(v)) if v.is_finite() => v.to_string(),
+            Some(x_stat::Value::DoubleValue(v)) => json_str(&v.to_string()),
+            Some(x_stat::Value::Uint64Value(v)) => v.to_string(),
+            Some(x_stat::Value::Int64Value(v)) => v.to_string(),
+            Some(x_stat::Value::StrValue(v)) => json_str(v),
+            Some(x_stat::Value::BytesValue(v)) => json_str(&format!("<{} bytes>", v.len())),
+            Some(x_stat::Value::RefValue(v)) => json_str(stat_name(*v as i64)),
+            None => continue,
+        };
+        args.push(format!(
+            "{}:{}",
+            json_str(stat_name(stat.metadata_id)),
+            value
+        ));
+    }
+    args.join(",")
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pjrt_sys::protos::tensorflow::profiler::{XEvent, XEventMetadata, XLine, XStatMetadata};
+
+    use super::*;
+
+    #[test]
+    fn json_str_escapes() {
+        assert_eq!(json_str("a\"b\\c\nd\te\u{1}"), r#""a\"b\\c\nd\te\u0001""#);
+    }
+
+    #[test]
+    fn chrome_trace() {
+        let plane = XPlane {
+            name: "/device:\"cpu\"".to_string(),
+            lines: vec![XLine {
+                id: 3,
+                name: "line".to_string(),
+                display_name: "main".to_string(),
+                timestamp_ns: 1000,
+                events: vec![
+                    XEvent {
+                        metadata_id: 1,
+                        duration_ps: 2_000_000,
+                        stats: vec![XStat {
+                            metadata_id: 7,
+                            value: Some(x_stat::Value::StrValue("x\ny".to_string())),
+                        }],
+                        data: Some(x_event::Data::OffsetPs(500_000)),
+                    },
+                    // aggregated events are skipped
+                    XEvent {
+                        metadata_id: 1,
+                        data: Some(x_event::Data::NumOccurrences(2)),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            event_metadata: [(
+                1,
+                XEventMetadata {
+                    id: 1,
+                    name: "add".to_string(),
+                    stats: vec![XStat {
+                        metadata_id: 8,
+                        value: Some(x_stat::Value::Int64Value(42)),
+                    }],
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            stat_metadata: [
+                (
+                    7,
+                    XStatMetadata {
+                        id: 7,
+                        name: "note".to_string(),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    8,
+                    XStatMetadata {
+                        id: 8,
+                        name: "flops".to_string(),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let xspace = XSpace {
+            planes: vec![plane],
+            ..Default::default()
+        };
+        let trace = xspace_to_chrome_trace(&xspace.encode_to_vec()).unwrap();
+        assert_eq!(
+            trace,
+            concat!(
+                r#"{"displayTimeUnit":"ns","traceEvents":["#,
+                r#"{"ph":"M","pid":0,"name":"process_name","args":{"name":"/device:\"cpu\""}},"#,
+                r#"{"ph":"M","pid":0,"tid":3,"name":"thread_name","args":{"name":"main"}},"#,
+                r#"{"ph":"X","pid":0,"tid":3,"ts":1.5,"dur":2,"name":"add","#,
+                r#""args":{"flops":42,"note":"x\ny"}}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn chrome_trace_invalid() {
+        assert!(xspace_to_chrome_trace(b"\xff").is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use std::fs;
 use std::path::Path;
 
+use pjrt_sys::protos::xla::HloModuleProto;
 use pjrt_sys::PJRT_Program;
+use prost::Message;
 
 use crate::{Error, Result};
 
@@ -9,6 +11,9 @@ use crate::{Error, Result};
 pub enum ProgramFormat {
     MLIR,
     HLO,
+    /// A serialized `HloModuleProtoWithConfig`, the format optimized programs
+    /// are returned in.
+    HLOWithConfig,
 }
 
 impl ProgramFormat {
@@ -16,6 +21,7 @@ impl ProgramFormat {
         match self {
             ProgramFormat::MLIR => "mlir",
             ProgramFormat::HLO => "hlo",
+            ProgramFormat::HLOWithConfig => "hlo_with_config",
         }
     }
 
@@ -23,6 +29,7 @@ impl ProgramFormat {
         match self {
             ProgramFormat::MLIR => b"mlir",
             ProgramFormat::HLO => b"hlo",
+            ProgramFormat::HLOWithConfig => b"hlo_with_config",
         }
     }
 }
@@ -34,6 +41,7 @@ impl TryFrom<&str> for ProgramFormat {
         match value {
             "mlir" => Ok(ProgramFormat::MLIR),
             "hlo" => Ok(ProgramFormat::HLO),
+            "hlo_with_config" => Ok(ProgramFormat::HLOWithConfig),
             _ => Err(Error::InvalidProgramFormat(value.to_string())),
         }
     }
@@ -77,4 +85,8 @@ impl Program {
         let code = fs::read(path)?;
         Ok(Program::new(ProgramFormat::HLO, code))
     }
+
+    pub fn from_hlo_module(module: HloModuleProto) -> Self {
+        Program::new(ProgramFormat::HLO, module.encode_to_vec())
+    }
 }
@@ -0,0 +1,290 @@
+use pjrt_sys::protos::xla::{self, ShapeProto};
+
+use crate::{Error, MemoryLayout, PrimitiveType, Result};
+
+/// The shape of a program parameter or result.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Array {
+        ty: PrimitiveType,
+        dims: Vec<i64>,
+        layout: Option<MemoryLayout>,
+    },
+    Tuple(Vec<Shape>),
+    Token,
+}
+
+impl Shape {
+    pub fn array(ty: PrimitiveType, dims: impl Into<Vec<i64>>) -> Self {
+        Shape::Array {
+            ty,
+            dims: dims.into(),
+            layout: None,
+        }
+    }
+
+    /// An array with the given device layout.
+    pub fn array_with_layout(
+        ty: PrimitiveType,
+        dims: impl Into<Vec<i64>>,
+        layout: MemoryLayout,
+    ) -> Self {
+        Shape::Array {
+            ty,
+            dims: dims.into(),
+            layout: Some(layout),
+        }
+    }
+
+    pub fn tuple(shapes: impl Into<Vec<Shape>>) -> Self {
+        Shape::Tuple(shapes.into())
+    }
+
+    pub fn is_tuple(&self) -> bool {
+        matches!(self, Shape::Tuple(_))
+    }
+
+    /// The number of arrays and tokens, which PJRT passes as flat buffers.
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Shape::Tuple(shapes) => shapes.iter().map(|s| s.leaf_count()).sum(),
+            _ => 1,
+        }
+    }
+
+    /// The arrays and tokens of the shape, in order.
+    pub fn leaves(&self) -> Vec<&Shape> {
+        match self {
+            Shape::Tuple(shapes) => shapes.iter().flat_map(|s| s.leaves()).collect(),
+            shape => vec![shape],
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a ShapeProto> for Shape {
+    type Error = Error;
+
+    fn try_from(shape: &'a ShapeProto) -> Result<Self> {
+        let ty = xla::PrimitiveType::try_from(shape.element_type)
+            .map_err(|_| Error::InvalidPrimitiveType(shape.element_type))?;
+        match ty {
+            xla::PrimitiveType::Tuple => Ok(Shape::Tuple(
+                shape
+                    .tuple_shapes
+                    .iter()
+                    .map(Shape::try_from)
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            xla::PrimitiveType::Token => Ok(Shape::Token),
+            ty => {
+                let layout = match &shape.layout {
+                    Some(l) if l.minor_to_major.len() != shape.dimensions.len() => {
+                        return Err(Error::InvalidMemoryLayout(format!(
+                            "minor_to_major {:?} doesn't match dims {:?}",
+                            l.minor_to_major, shape.dimensions
+                        )))
+                    }
+                    l => l.as_ref().map(|l| MemoryLayout::from(l.as_ref())),
+                };
+                Ok(Shape::Array {
+                    ty: PrimitiveType::try_from(ty)?,
+                    dims: shape.dimensions.clone(),
+                    layout,
+                })
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Shape> for ShapeProto {
+    type Error = Error;
+
+    fn try_from(shape: &'a Shape) -> Result<Self> {
+        match shape {
+            Shape::Array { ty, dims, layout } => {
+                let layout = match layout {
+                    Some(layout) => {
+                        let elem_size = ty.try_into_dtype()?.size();
+                        Some(Box::new(layout.to_proto(dims, elem_size)?))
+                    }
+                    None => None,
+                };
+                Ok(ShapeProto {
+                    element_type: xla::PrimitiveType::from(*ty) as i32,
+                    dimensions: dims.clone(),
+                    layout,
+                    ..Default::default()
+                })
+            }
+            Shape::Tuple(shapes) => Ok(ShapeProto {
+                element_type: xla::PrimitiveType::Tuple as i32,
+                tuple_shapes: shapes
+                    .iter()
+                    .map(ShapeProto::try_from)
+                    .collect::<Result<Vec<_>>>()?,
+                ..Default::default()
+            }),
+            Shape::Token => Ok(ShapeProto {
+                element_type: xla::PrimitiveType::Token as i32,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Values arranged as the leaves of a [`Shape`], e.g. the buffers of a
+/// tuple result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeTree<T> {
+    Leaf(T),
+    Tuple(Vec<ShapeTree<T>>),
+}
+
+impl<T> ShapeTree<T> {
+    /// Arranges `leaves`, in order, as the leaves of `shape`.
+    pub fn from_leaves(shape: &Shape, leaves: impl IntoIterator<Item = T>) -> Result<Self> {
+        let leaves = leaves.into_iter().collect::<Vec<_>>();
+        if leaves.len() != shape.leaf_count() {
+            return Err(Error::LeafCountMismatch {
+                expected: shape.leaf_count(),
+                actual: leaves.len(),
+            });
+        }
+        Ok(Self::take_leaves(shape, &mut leaves.into_iter()))
+    }
+
+    fn take_leaves(shape: &Shape, leaves: &mut impl Iterator<Item = T>) -> Self {
+        match shape {
+            Shape::Tuple(shapes) => ShapeTree::Tuple(
+                shapes
+                    .iter()
+                    .map(|s| Self::take_leaves(s, leaves))
+                    .collect(),
+            ),
+            _ => ShapeTree::Leaf(leaves.next().expect("leaf count checked")),
+        }
+    }
+
+    /// The value, if the tree is a single leaf.
+    pub fn leaf(&self) -> Option<&T> {
+        match self {
+            ShapeTree::Leaf(v) => Some(v),
+            ShapeTree::Tuple(_) => None,
+        }
+    }
+
+    pub fn into_leaf(self) -> Option<T> {
+        match self {
+            ShapeTree::Leaf(v) => Some(v),
+            ShapeTree::Tuple(_) => None,
+        }
+    }
+
+    /// The subtree at `index`, the path of tuple element indices.
+    pub fn get(&self, index: &[usize]) -> Option<&ShapeTree<T>> {
+        match (index.split_first(), self) {
+            (None, tree) => Some(tree),
+            (Some((i, rest)), ShapeTree::Tuple(elements)) => elements.get(*i)?.get(rest),
+            (Some(_), ShapeTree::Leaf(_)) => None,
+        }
+    }
+
+    /// The leaves in order, as PJRT passes them.
+    pub fn leaves(&self) -> Vec<&T> {
+        match self {
+            ShapeTree::Leaf(v) => vec![v],
+            ShapeTree::Tuple(elements) => elements.iter().flat_map(|e| e.leaves()).collect(),
+        }
+    }
+
+    pub fn into_leaves(self) -> Vec<T> {
+        match self {
+            ShapeTree::Leaf(v) => vec![v],
+            ShapeTree::Tuple(elements) => {
+                elements.into_iter().flat_map(|e| e.into_leaves()).collect()
+            }
+        }
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> ShapeTree<U> {
+        self.map_with(&mut f)
+    }
+
+    fn map_with<U>(self, f: &mut impl FnMut(T) -> U) -> ShapeTree<U> {
+        match self {
+            ShapeTree::Leaf(v) => ShapeTree::Leaf(f(v)),
+            ShapeTree::Tuple(elements) => {
+                ShapeTree::Tuple(elements.into_iter().map(|e| e.map_with(f)).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (f32[2], (s32[], token))
+    fn nested() -> Shape {
+        Shape::tuple([
+            Shape::array(PrimitiveType::F32, [2]),
+            Shape::tuple([Shape::array(PrimitiveType::S32, []), Shape::Token]),
+        ])
+    }
+
+    #[test]
+    fn from_leaves() {
+        let tree = ShapeTree::from_leaves(&nested(), [1, 2, 3]).unwrap();
+        assert_eq!(
+            tree,
+            ShapeTree::Tuple(vec![
+                ShapeTree::Leaf(1),
+                ShapeTree::Tuple(vec![ShapeTree::Leaf(2), ShapeTree::Leaf(3)]),
+            ])
+        );
+        assert_eq!(tree.leaves(), vec![&1, &2, &3]);
+        assert_eq!(tree.into_leaves(), vec![1, 2, 3]);
+
+        let tree = ShapeTree::from_leaves(&Shape::array(PrimitiveType::F32, []), [7]).unwrap();
+        assert_eq!(tree.leaf(), Some(&7));
+        assert_eq!(tree.into_leaf(), Some(7));
+    }
+
+    #[test]
+    fn from_leaves_count_mismatch() {
+        for leaves in [vec![1, 2], vec![1, 2, 3, 4]] {
+            let actual = leaves.len();
+            match ShapeTree::from_leaves(&nested(), leaves) {
+                Err(Error::LeafCountMismatch {
+                    expected: 3,
+                    actual: a,
+                }) => assert_eq!(a, actual),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn get() {
+        let tree = ShapeTree::from_leaves(&nested(), [1, 2, 3]).unwrap();
+        assert_eq!(tree.get(&[]), Some(&tree));
+        assert_eq!(tree.get(&[0]), Some(&ShapeTree::Leaf(1)));
+        assert_eq!(tree.get(&[1, 1]), Some(&ShapeTree::Leaf(3)));
+        assert_eq!(tree.get(&[1]).and_then(|t| t.leaf()), None);
+        assert_eq!(tree.get(&[2]), None);
+        assert_eq!(tree.get(&[0, 0]), None);
+    }
+
+    #[test]
+    fn map() {
+        let tree = ShapeTree::from_leaves(&nested(), [1, 2, 3]).unwrap();
+        let mut visited = vec![];
+        let mapped = tree.map(|v| {
+            visited.push(v);
+            v.to_string()
+        });
+        assert_eq!(visited, vec![1, 2, 3]);
+        assert_eq!(mapped.get(&[1, 0]), Some(&ShapeTree::Leaf("2".to_string())));
+        assert_eq!(mapped.into_leaves(), vec!["1", "2", "3"]);
+    }
+}
@@ -9,7 +9,7 @@ use pjrt_sys::{
     PJRT_TopologyDescription_Serialize_Args,
 };
 
-use crate::{utils, Api, DeviceDescription, NamedValue, NamedValueMap, Result};
+use crate::{utils, Api, DeviceDescription, Error, NamedValue, NamedValueMap, Result};
 
 pub struct TopologyDescription {
     pub(crate) api: Api,
@@ -46,63 +46,86 @@ impl TopologyDescription {
     }
 
     pub fn platform_name(&self) -> Cow<'_, str> {
+        self.try_platform_name()
+            .expect("PJRT_TopologyDescription_PlatformName")
+    }
+
+    pub fn try_platform_name(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_TopologyDescription_PlatformName_Args::new();
         args.topology = self.ptr;
-        args = self
-            .api
-            .PJRT_TopologyDescription_PlatformName(args)
-            .expect("PJRT_TopologyDescription_PlatformName");
-        utils::str_from_raw(args.platform_name, args.platform_name_size)
+        args = self.api.PJRT_TopologyDescription_PlatformName(args)?;
+        Ok(utils::str_from_raw(
+            args.platform_name,
+            args.platform_name_size,
+        ))
     }
 
     pub fn platform_version(&self) -> Cow<'_, str> {
+        self.try_platform_version()
+            .expect("PJRT_TopologyDescription_PlatformVersion")
+    }
+
+    pub fn try_platform_version(&self) -> Result<Cow<'_, str>> {
         let mut args = PJRT_TopologyDescription_PlatformVersion_Args::new();
         args.topology = self.ptr;
-        args = self
-            .api
-            .PJRT_TopologyDescription_PlatformVersion(args)
-            .expect("PJRT_TopologyDescription_PlatformVersion");
-        utils::str_from_raw(args.platform_version, args.platform_version_size)
+        args = self.api.PJRT_TopologyDescription_PlatformVersion(args)?;
+        Ok(utils::str_from_raw(
+            args.platform_version,
+            args.platform_version_size,
+        ))
     }
 
     pub fn device_descriptions(&self) -> Vec<DeviceDescription> {
+        self.try_device_descriptions()
+            .expect("PJRT_TopologyDescription_GetDeviceDescriptions")
+    }
+
+    pub fn try_device_descriptions(&self) -> Result<Vec<DeviceDescription>> {
         let mut args = PJRT_TopologyDescription_GetDeviceDescriptions_Args::new();
         args.topology = self.ptr;
         args = self
             .api
-            .PJRT_TopologyDescription_GetDeviceDescriptions(args)
-            .expect("PJRT_TopologyDescription_GetDeviceDescriptions");
+            .PJRT_TopologyDescription_GetDeviceDescriptions(args)?;
         let descriptions =
             unsafe { slice::from_raw_parts(args.descriptions, args.num_descriptions) };
-        descriptions
+        Ok(descriptions
             .iter()
             .map(|ptr| DeviceDescription::wrap(&self.api, *ptr))
-            .collect()
+            .collect())
     }
 
     pub fn attributes(&self) -> NamedValueMap {
+        self.try_attributes()
+            .expect("PJRT_TopologyDescription_Attributes")
+    }
+
+    pub fn try_attributes(&self) -> Result<NamedValueMap> {
         let mut args = PJRT_TopologyDescription_Attributes_Args::new();
         args.topology = self.ptr;
-        args = self
-            .api
-            .PJRT_TopologyDescription_Attributes(args)
-            .expect("PJRT_TopologyDescription_Attributes");
-        utils::to_named_value_map(args.attributes, args.num_attributes)
+        args = self.api.PJRT_TopologyDescription_Attributes(args)?;
+        Ok(utils::to_named_value_map(
+            args.attributes,
+            args.num_attributes,
+        ))
     }
 
     pub fn serialize(&self) -> SerializedTopology {
+        self.try_serialize()
+            .expect("PJRT_TopologyDescription_Serialize")
+    }
+
+    pub fn try_serialize(&self) -> Result<SerializedTopology> {
         let mut args = PJRT_TopologyDescription_Serialize_Args::new();
         args.topology = self.ptr;
-        args = self
-            .api
-            .PJRT_TopologyDescription_Serialize(args)
-            .expect("PJRT_TopologyDescription_Serialize");
-        SerializedTopology {
+        args = self.api.PJRT_TopologyDescription_Serialize(args)?;
+        Ok(SerializedTopology {
             ptr: args.serialized_topology,
-            deleter: args.serialized_topology_deleter.expect("topology_deleter"),
+            deleter: args
+                .serialized_topology_deleter
+                .ok_or(Error::NullFunctionPointer("serialized_topology_deleter"))?,
             data_ptr: args.serialized_bytes as *const u8,
             data_len: args.serialized_bytes_size,
-        }
+        })
     }
 }
 
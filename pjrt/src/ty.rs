@@ -1,6 +1,7 @@
 use std::any::Any;
 use std::fmt::Debug;
 
+use pjrt_sys::protos::xla;
 use pjrt_sys::{
     PJRT_Buffer_Type, PJRT_Buffer_Type_PJRT_Buffer_Type_BF16,
     PJRT_Buffer_Type_PJRT_Buffer_Type_C128, PJRT_Buffer_Type_PJRT_Buffer_Type_C64,
@@ -245,6 +246,166 @@ impl ElemType for num_complex::Complex<f64> {
     type Type = C128;
 }
 
+// how an 8 bit float format encodes non finite values
+enum F8Nan {
+    // all ones exponent is inf or nan, as in IEEE formats
+    Ieee,
+    // all ones exponent and mantissa is nan, no inf
+    AllOnes,
+    // negative zero is nan, no inf
+    NegativeZero,
+}
+
+fn f8_to_f32(bits: u8, exponent_bits: u32, bias: i32, nan: F8Nan) -> f32 {
+    let mantissa_bits = 7 - exponent_bits;
+    let exponent_mask = (1u8 << exponent_bits) - 1;
+    let mantissa_mask = (1u8 << mantissa_bits) - 1;
+    let sign = if bits & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> mantissa_bits) & exponent_mask;
+    let mantissa = bits & mantissa_mask;
+    match nan {
+        F8Nan::Ieee if exponent == exponent_mask => {
+            return if mantissa == 0 {
+                sign * f32::INFINITY
+            } else {
+                f32::NAN
+            };
+        }
+        F8Nan::AllOnes if exponent == exponent_mask && mantissa == mantissa_mask => {
+            return f32::NAN;
+        }
+        F8Nan::NegativeZero if bits == 0x80 => return f32::NAN,
+        _ => {}
+    }
+    let fraction = mantissa as f32 / (1u32 << mantissa_bits) as f32;
+    if exponent == 0 {
+        sign * fraction * 2f32.powi(1 - bias)
+    } else {
+        sign * (1.0 + fraction) * 2f32.powi(exponent as i32 - bias)
+    }
+}
+
+macro_rules! impl_f8_type {
+    ($T:ident, $E:ident, $name:literal, $exponent_bits:literal, $bias:literal, $nan:ident) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $T;
+
+        impl Type for $T {
+            const NAME: &'static str = $name;
+            const PRIMITIVE_TYPE: PrimitiveType = PrimitiveType::$T;
+            const TYPE: Self = $T;
+            type ElemType = $E;
+        }
+
+        #[doc = concat!("A `", $name, "` value, stored as its raw bits.")]
+        #[allow(non_camel_case_types)]
+        #[repr(transparent)]
+        #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct $E(u8);
+
+        impl $E {
+            pub const fn from_bits(bits: u8) -> Self {
+                Self(bits)
+            }
+
+            pub const fn to_bits(self) -> u8 {
+                self.0
+            }
+
+            pub fn to_f32(self) -> f32 {
+                f8_to_f32(self.0, $exponent_bits, $bias, F8Nan::$nan)
+            }
+        }
+
+        impl From<$E> for f32 {
+            fn from(value: $E) -> Self {
+                value.to_f32()
+            }
+        }
+
+        impl ElemType for $E {
+            type Type = $T;
+        }
+    };
+}
+
+impl_f8_type!(F8E5M2, f8e5m2, "f8e5m2", 5, 15, Ieee);
+impl_f8_type!(F8E4M3FN, f8e4m3fn, "f8e4m3fn", 4, 7, AllOnes);
+impl_f8_type!(
+    F8E4M3B11FNUZ,
+    f8e4m3b11fnuz,
+    "f8e4m3b11fnuz",
+    4,
+    11,
+    NegativeZero
+);
+impl_f8_type!(F8E5M2FNUZ, f8e5m2fnuz, "f8e5m2fnuz", 5, 16, NegativeZero);
+impl_f8_type!(F8E4M3FNUZ, f8e4m3fnuz, "f8e4m3fnuz", 4, 8, NegativeZero);
+
+/// Integer types narrower than a byte. The host stores them packed, several
+/// per byte starting from the lowest bits.
+pub trait PackedType: Sized + Copy + Debug + 'static {
+    const NAME: &'static str;
+    const PRIMITIVE_TYPE: PrimitiveType;
+    const BITS: usize;
+    const PER_BYTE: usize = 8 / Self::BITS;
+    /// The type elements are unpacked to.
+    type ElemType: Copy + Debug + 'static;
+
+    /// Truncates `value` to its lowest `BITS` bits.
+    fn pack(value: Self::ElemType) -> u8;
+
+    /// Reads an element from the lowest `BITS` bits of `bits`.
+    fn unpack(bits: u8) -> Self::ElemType;
+}
+
+macro_rules! impl_packed_type {
+    ($T:ident, $P:ident, $name:literal, $bits:literal,i8) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $T;
+
+        impl PackedType for $T {
+            const NAME: &'static str = $name;
+            const PRIMITIVE_TYPE: PrimitiveType = PrimitiveType::$P;
+            const BITS: usize = $bits;
+            type ElemType = i8;
+
+            fn pack(value: i8) -> u8 {
+                value as u8 & ((1 << $bits) - 1)
+            }
+
+            fn unpack(bits: u8) -> i8 {
+                // sign extend from the element's highest bit
+                ((bits << (8 - $bits)) as i8) >> (8 - $bits)
+            }
+        }
+    };
+    ($T:ident, $P:ident, $name:literal, $bits:literal,u8) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $T;
+
+        impl PackedType for $T {
+            const NAME: &'static str = $name;
+            const PRIMITIVE_TYPE: PrimitiveType = PrimitiveType::$P;
+            const BITS: usize = $bits;
+            type ElemType = u8;
+
+            fn pack(value: u8) -> u8 {
+                value & ((1 << $bits) - 1)
+            }
+
+            fn unpack(bits: u8) -> u8 {
+                bits & ((1 << $bits) - 1)
+            }
+        }
+    };
+}
+
+impl_packed_type!(I4, S4, "i4", 4, i8);
+impl_packed_type!(U4, U4, "u4", 4, u8);
+impl_packed_type!(I2, S2, "i2", 2, i8);
+impl_packed_type!(U2, U2, "u2", 2, u8);
+
 #[repr(i32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PrimitiveType {
@@ -328,11 +489,11 @@ impl PrimitiveType {
             PrimitiveType::BF16 => Ok(BF16.boxed_dtype()),
             PrimitiveType::C64 => Ok(C64.boxed_dtype()),
             PrimitiveType::C128 => Ok(C128.boxed_dtype()),
-            PrimitiveType::F8E5M2 => Err(Error::Unimplemeted),
-            PrimitiveType::F8E4M3FN => Err(Error::Unimplemeted),
-            PrimitiveType::F8E4M3B11FNUZ => Err(Error::Unimplemeted),
-            PrimitiveType::F8E5M2FNUZ => Err(Error::Unimplemeted),
-            PrimitiveType::F8E4M3FNUZ => Err(Error::Unimplemeted),
+            PrimitiveType::F8E5M2 => Ok(F8E5M2.boxed_dtype()),
+            PrimitiveType::F8E4M3FN => Ok(F8E4M3FN.boxed_dtype()),
+            PrimitiveType::F8E4M3B11FNUZ => Ok(F8E4M3B11FNUZ.boxed_dtype()),
+            PrimitiveType::F8E5M2FNUZ => Ok(F8E5M2FNUZ.boxed_dtype()),
+            PrimitiveType::F8E4M3FNUZ => Ok(F8E4M3FNUZ.boxed_dtype()),
             PrimitiveType::S4 => Err(Error::Unimplemeted),
             PrimitiveType::U4 => Err(Error::Unimplemeted),
             PrimitiveType::Token => Err(Error::Unimplemeted),
@@ -368,7 +529,7 @@ impl TryFrom<PJRT_Buffer_Type> for PrimitiveType {
             PJRT_Buffer_Type_PJRT_Buffer_Type_F8E5M2 => Ok(Self::F8E5M2),
             PJRT_Buffer_Type_PJRT_Buffer_Type_F8E4M3FN => Ok(Self::F8E4M3FN),
             PJRT_Buffer_Type_PJRT_Buffer_Type_F8E4M3B11FNUZ => Ok(Self::F8E4M3B11FNUZ),
-            PJRT_Buffer_Type_PJRT_Buffer_Type_F8E5M2FNUZ => Ok(Self::F8E4M3FNUZ),
+            PJRT_Buffer_Type_PJRT_Buffer_Type_F8E5M2FNUZ => Ok(Self::F8E5M2FNUZ),
             PJRT_Buffer_Type_PJRT_Buffer_Type_F8E4M3FNUZ => Ok(Self::F8E4M3FNUZ),
             PJRT_Buffer_Type_PJRT_Buffer_Type_S4 => Ok(Self::S4),
             PJRT_Buffer_Type_PJRT_Buffer_Type_U4 => Ok(Self::U4),
@@ -380,6 +541,76 @@ impl TryFrom<PJRT_Buffer_Type> for PrimitiveType {
     }
 }
 
+impl From<PrimitiveType> for xla::PrimitiveType {
+    fn from(value: PrimitiveType) -> Self {
+        match value {
+            PrimitiveType::Invalid => Self::Invalid,
+            PrimitiveType::Pred => Self::Pred,
+            PrimitiveType::S8 => Self::S8,
+            PrimitiveType::S16 => Self::S16,
+            PrimitiveType::S32 => Self::S32,
+            PrimitiveType::S64 => Self::S64,
+            PrimitiveType::U8 => Self::U8,
+            PrimitiveType::U16 => Self::U16,
+            PrimitiveType::U32 => Self::U32,
+            PrimitiveType::U64 => Self::U64,
+            PrimitiveType::F16 => Self::F16,
+            PrimitiveType::F32 => Self::F32,
+            PrimitiveType::F64 => Self::F64,
+            PrimitiveType::BF16 => Self::Bf16,
+            PrimitiveType::C64 => Self::C64,
+            PrimitiveType::C128 => Self::C128,
+            PrimitiveType::F8E5M2 => Self::F8e5m2,
+            PrimitiveType::F8E4M3FN => Self::F8e4m3fn,
+            PrimitiveType::F8E4M3B11FNUZ => Self::F8e4m3b11fnuz,
+            PrimitiveType::F8E5M2FNUZ => Self::F8e5m2fnuz,
+            PrimitiveType::F8E4M3FNUZ => Self::F8e4m3fnuz,
+            PrimitiveType::S4 => Self::S4,
+            PrimitiveType::U4 => Self::U4,
+            PrimitiveType::Token => Self::Token,
+            PrimitiveType::S2 => Self::S2,
+            PrimitiveType::U2 => Self::U2,
+        }
+    }
+}
+
+impl TryFrom<xla::PrimitiveType> for PrimitiveType {
+    type Error = Error;
+
+    fn try_from(value: xla::PrimitiveType) -> Result<Self> {
+        match value {
+            xla::PrimitiveType::Invalid => Ok(Self::Invalid),
+            xla::PrimitiveType::Pred => Ok(Self::Pred),
+            xla::PrimitiveType::S8 => Ok(Self::S8),
+            xla::PrimitiveType::S16 => Ok(Self::S16),
+            xla::PrimitiveType::S32 => Ok(Self::S32),
+            xla::PrimitiveType::S64 => Ok(Self::S64),
+            xla::PrimitiveType::U8 => Ok(Self::U8),
+            xla::PrimitiveType::U16 => Ok(Self::U16),
+            xla::PrimitiveType::U32 => Ok(Self::U32),
+            xla::PrimitiveType::U64 => Ok(Self::U64),
+            xla::PrimitiveType::F16 => Ok(Self::F16),
+            xla::PrimitiveType::F32 => Ok(Self::F32),
+            xla::PrimitiveType::F64 => Ok(Self::F64),
+            xla::PrimitiveType::Bf16 => Ok(Self::BF16),
+            xla::PrimitiveType::C64 => Ok(Self::C64),
+            xla::PrimitiveType::C128 => Ok(Self::C128),
+            xla::PrimitiveType::F8e5m2 => Ok(Self::F8E5M2),
+            xla::PrimitiveType::F8e4m3fn => Ok(Self::F8E4M3FN),
+            xla::PrimitiveType::F8e4m3b11fnuz => Ok(Self::F8E4M3B11FNUZ),
+            xla::PrimitiveType::F8e5m2fnuz => Ok(Self::F8E5M2FNUZ),
+            xla::PrimitiveType::F8e4m3fnuz => Ok(Self::F8E4M3FNUZ),
+            xla::PrimitiveType::S4 => Ok(Self::S4),
+            xla::PrimitiveType::U4 => Ok(Self::U4),
+            xla::PrimitiveType::Token => Ok(Self::Token),
+            xla::PrimitiveType::S2 => Ok(Self::S2),
+            xla::PrimitiveType::U2 => Ok(Self::U2),
+            // tuples and opaque values have no buffer type
+            _ => Err(Error::InvalidPrimitiveType(value as i32)),
+        }
+    }
+}
+
 pub trait DType {
     fn name(&self) -> &'static str;
     fn primitive_type(&self) -> PrimitiveType;
@@ -430,3 +661,47 @@ impl<T: DType> AsDType for T {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f8e5m2_to_f32() {
+        let f = |bits| f8e5m2::from_bits(bits).to_f32();
+        assert_eq!(f(0x00), 0.0);
+        assert_eq!(f(0x3c), 1.0);
+        assert_eq!(f(0xc0), -2.0);
+        assert_eq!(f(0x7b), 57344.0);
+        // smallest subnormal
+        assert_eq!(f(0x01), 2f32.powi(-16));
+        assert_eq!(f(0x7c), f32::INFINITY);
+        assert_eq!(f(0xfc), f32::NEG_INFINITY);
+        assert!(f(0x7d).is_nan());
+    }
+
+    #[test]
+    fn f8e4m3fn_to_f32() {
+        let f = |bits| f8e4m3fn::from_bits(bits).to_f32();
+        assert_eq!(f(0x38), 1.0);
+        assert_eq!(f(0xb8), -1.0);
+        // no inf, all ones exponent is still finite
+        assert_eq!(f(0x7e), 448.0);
+        assert_eq!(f(0x01), 2f32.powi(-9));
+        assert!(f(0x7f).is_nan());
+        assert!(f(0xff).is_nan());
+    }
+
+    #[test]
+    fn fnuz_to_f32() {
+        assert_eq!(f8e4m3fnuz::from_bits(0x40).to_f32(), 1.0);
+        assert_eq!(f8e5m2fnuz::from_bits(0x40).to_f32(), 1.0);
+        assert_eq!(f8e4m3b11fnuz::from_bits(0x58).to_f32(), 1.0);
+        assert_eq!(f8e4m3fnuz::from_bits(0x7f).to_f32(), 240.0);
+        // negative zero is nan, positive zero is zero
+        assert!(f8e4m3fnuz::from_bits(0x80).to_f32().is_nan());
+        assert!(f8e5m2fnuz::from_bits(0x80).to_f32().is_nan());
+        assert!(f8e4m3b11fnuz::from_bits(0x80).to_f32().is_nan());
+        assert_eq!(f8e4m3fnuz::from_bits(0x00).to_f32(), 0.0);
+    }
+}
@@ -0,0 +1,120 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use bon::bon;
+use pjrt_sys::PJRT_Buffer;
+
+use crate::{
+    Buffer, Error, ExecutionInput, HostBuffer, MemoryLayout, Result, Type, TypedHostBuffer,
+};
+
+/// The rank of a [`TypedBuffer`] whose rank is only known at runtime.
+pub const DYN_RANK: usize = usize::MAX;
+
+/// A [`Buffer`] whose element type, and rank unless it is [`DYN_RANK`], are
+/// checked once when it is created.
+pub struct TypedBuffer<T: Type, const RANK: usize = DYN_RANK> {
+    buffer: Buffer,
+    ty: PhantomData<T>,
+}
+
+impl<T: Type, const RANK: usize> TryFrom<Buffer> for TypedBuffer<T, RANK> {
+    type Error = Error;
+
+    fn try_from(buffer: Buffer) -> Result<Self> {
+        let ty = buffer.try_primitive_type()?;
+        if ty != T::PRIMITIVE_TYPE {
+            return Err(Error::ElemTypeMismatch {
+                expected: T::PRIMITIVE_TYPE,
+                actual: ty,
+            });
+        }
+        if RANK != DYN_RANK {
+            let rank = buffer.try_dims()?.len();
+            if rank != RANK {
+                return Err(Error::RankMismatch {
+                    expected: RANK,
+                    actual: rank,
+                });
+            }
+        }
+        Ok(Self {
+            buffer,
+            ty: PhantomData,
+        })
+    }
+}
+
+impl<T: Type, const RANK: usize> Deref for TypedBuffer<T, RANK> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+impl<T: Type, const RANK: usize> From<TypedBuffer<T, RANK>> for Buffer {
+    fn from(buffer: TypedBuffer<T, RANK>) -> Self {
+        buffer.buffer
+    }
+}
+
+#[bon]
+impl<T: Type, const RANK: usize> TypedBuffer<T, RANK> {
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn into_buffer(self) -> Buffer {
+        self.buffer
+    }
+
+    #[builder(finish_fn = copy)]
+    pub async fn to_host(&self, host_layout: Option<MemoryLayout>) -> Result<TypedHostBuffer<T>> {
+        let buf = self
+            .buffer
+            .to_host()
+            .maybe_host_layout(host_layout)
+            .copy()
+            .await?;
+        Self::typed_host(buf)
+    }
+
+    #[builder(finish_fn = copy)]
+    pub fn to_host_sync(&self, host_layout: Option<MemoryLayout>) -> Result<TypedHostBuffer<T>> {
+        let buf = self
+            .buffer
+            .to_host_sync()
+            .maybe_host_layout(host_layout)
+            .copy()?;
+        Self::typed_host(buf)
+    }
+
+    fn typed_host(buf: HostBuffer) -> Result<TypedHostBuffer<T>> {
+        let actual = buf.primitive_type();
+        buf.into_typed().ok_or(Error::ElemTypeMismatch {
+            expected: T::PRIMITIVE_TYPE,
+            actual,
+        })
+    }
+}
+
+impl<T: Type, const RANK: usize> ExecutionInput for TypedBuffer<T, RANK> {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.buffer.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        true
+    }
+}
+
+impl<T: Type, const RANK: usize> ExecutionInput for &TypedBuffer<T, RANK> {
+    fn buffer_ptr(&self) -> *mut PJRT_Buffer {
+        self.buffer.ptr
+    }
+
+    fn donatable(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use pjrt_sys::protos::{xla, FILE_DESCRIPTOR_SET};
+use prost::encoding::{encode_key, encode_varint, WireType};
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+
+use crate::{Error, Result};
+
+struct Descriptors {
+    // DebugOptions fields by name
+    fields: HashMap<String, FieldDescriptorProto>,
+    // full names of the map entry messages of DebugOptions
+    map_entries: HashSet<String>,
+    // enums by full name, e.g. `.xla.DebugOptions.ShapeChecks`
+    enums: HashMap<String, EnumDescriptorProto>,
+}
+
+fn collect_enums(
+    prefix: &str,
+    messages: &[DescriptorProto],
+    enums: &mut HashMap<String, EnumDescriptorProto>,
+) {
+    for message in messages {
+        let name = format!("{}.{}", prefix, message.name());
+        for e in message.enum_type.iter() {
+            enums.insert(format!("{}.{}", name, e.name()), e.clone());
+        }
+        collect_enums(&name, &message.nested_type, enums);
+    }
+}
+
+fn descriptors() -> &'static Descriptors {
+    static DESCRIPTORS: OnceLock<Descriptors> = OnceLock::new();
+    DESCRIPTORS.get_or_init(|| {
+        let set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).expect("FILE_DESCRIPTOR_SET");
+        let mut fields = HashMap::new();
+        let mut map_entries = HashSet::new();
+        let mut enums = HashMap::new();
+        for file in set.file.iter() {
+            let prefix = format!(".{}", file.package());
+            for e in file.enum_type.iter() {
+                enums.insert(format!("{}.{}", prefix, e.name()), e.clone());
+            }
+            collect_enums(&prefix, &file.message_type, &mut enums);
+            if file.package() != "xla" {
+                continue;
+            }
+            if let Some(message) = file
+                .message_type
+                .iter()
+                .find(|m| m.name() == "DebugOptions")
+            {
+                for field in message.field.iter() {
+                    fields.insert(field.name().to_string(), field.clone());
+                }
+                for nested in message.nested_type.iter() {
+                    if nested.options.as_ref().is_some_and(|o| o.map_entry()) {
+                        map_entries.insert(format!(".xla.DebugOptions.{}", nested.name()));
+                    }
+                }
+            }
+        }
+        Descriptors {
+            fields,
+            map_entries,
+            enums,
+        }
+    })
+}
+
+fn invalid(name: &str, value: &str) -> Error {
+    Error::InvalidXlaFlag(format!("--{}={}", name, value))
+}
+
+fn encode_value(
+    field: &FieldDescriptorProto,
+    value: &str,
+    enums: &HashMap<String, EnumDescriptorProto>,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    let name = field.name();
+    let tag = field.number() as u32;
+    match field.r#type() {
+        Type::Bool => {
+            let v = match value {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(invalid(name, value)),
+            };
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v as u64, buf);
+        }
+        Type::Int32 => {
+            let v = value.parse::<i32>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v as i64 as u64, buf);
+        }
+        Type::Int64 => {
+            let v = value.parse::<i64>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v as u64, buf);
+        }
+        Type::Uint32 => {
+            let v = value.parse::<u32>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v as u64, buf);
+        }
+        Type::Uint64 => {
+            let v = value.parse::<u64>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v, buf);
+        }
+        Type::Float => {
+            let v = value.parse::<f32>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::ThirtyTwoBit, buf);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Type::Double => {
+            let v = value.parse::<f64>().map_err(|_| invalid(name, value))?;
+            encode_key(tag, WireType::SixtyFourBit, buf);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Type::String => {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.extend_from_slice(value.as_bytes());
+        }
+        // enum values are given by name, in any case, or by number
+        Type::Enum => {
+            let e = enums
+                .get(field.type_name())
+                .ok_or_else(|| invalid(name, value))?;
+            let v = match value.parse::<i32>() {
+                Ok(v) => v,
+                Err(_) => e
+                    .value
+                    .iter()
+                    .find(|v| v.name().eq_ignore_ascii_case(value))
+                    .map(|v| v.number())
+                    .ok_or_else(|| invalid(name, value))?,
+            };
+            encode_key(tag, WireType::Varint, buf);
+            encode_varint(v as i64 as u64, buf);
+        }
+        _ => {
+            return Err(Error::InvalidXlaFlag(format!(
+                "--{} can't be set from a flag",
+                name
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Merges `--name=value` flags, as in `XLA_FLAGS`, into `options`. Repeated
+/// fields take comma separated values and bool flags may omit the value.
+pub(crate) fn merge_xla_flags(options: &mut xla::DebugOptions, flags: &str) -> Result<()> {
+    let descriptors = descriptors();
+    let mut buf = Vec::new();
+    for flag in flags.split_whitespace() {
+        let flag = flag
+            .strip_prefix("--")
+            .or_else(|| flag.strip_prefix('-'))
+            .ok_or_else(|| Error::InvalidXlaFlag(flag.to_string()))?;
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag, None),
+        };
+        let field = descriptors
+            .fields
+            .get(name)
+            .ok_or_else(|| Error::UnknownXlaFlag(name.to_string()))?;
+        // XLA parses these from its own syntax, e.g. `key=value;key`
+        if descriptors.map_entries.contains(field.type_name()) {
+            return Err(Error::InvalidXlaFlag(format!(
+                "--{} is a map, set it with DebugOptions::proto_mut",
+                name
+            )));
+        }
+        let value = match value {
+            Some(value) => value,
+            None if field.r#type() == Type::Bool => "true",
+            None => return Err(invalid(name, "")),
+        };
+        if field.label() == Label::Repeated {
+            for value in value.split(',').filter(|v| !v.is_empty()) {
+                encode_value(field, value, &descriptors.enums, &mut buf)?;
+            }
+        } else {
+            encode_value(field, value, &descriptors.enums, &mut buf)?;
+        }
+    }
+    options.merge(buf.as_slice())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use xla::debug_options::{CollectiveOpType, ShapeChecks};
+
+    use super::*;
+
+    fn parse(flags: &str) -> Result<xla::DebugOptions> {
+        let mut options = xla::DebugOptions::default();
+        merge_xla_flags(&mut options, flags)?;
+        Ok(options)
+    }
+
+    #[test]
+    fn scalars() {
+        let options = parse(
+            "--xla_dump_to=/tmp/dump --xla_cpu_enable_fast_math=true \
+             --xla_gpu_autotune_level=2 -xla_gpu_all_reduce_combine_threshold_bytes=-1",
+        )
+        .unwrap();
+        assert_eq!(options.xla_dump_to, "/tmp/dump");
+        assert!(options.xla_cpu_enable_fast_math);
+        assert_eq!(options.xla_gpu_autotune_level, 2);
+        assert_eq!(options.xla_gpu_all_reduce_combine_threshold_bytes, -1);
+    }
+
+    #[test]
+    fn bare_bool() {
+        let options = parse("--xla_dump_hlo_as_text --xla_cpu_enable_fast_math=0").unwrap();
+        assert!(options.xla_dump_hlo_as_text);
+        assert!(!options.xla_cpu_enable_fast_math);
+        assert!(matches!(
+            parse("--xla_dump_to"),
+            Err(Error::InvalidXlaFlag(_))
+        ));
+    }
+
+    #[test]
+    fn enums() {
+        for value in ["RUNTIME", "runtime", "1"] {
+            let options = parse(&format!("--xla_gpu_shape_checks={}", value)).unwrap();
+            assert_eq!(options.xla_gpu_shape_checks(), ShapeChecks::Runtime);
+        }
+        assert!(matches!(
+            parse("--xla_gpu_shape_checks=sometimes"),
+            Err(Error::InvalidXlaFlag(_))
+        ));
+    }
+
+    #[test]
+    fn repeated() {
+        let options = parse(
+            "--xla_disable_hlo_passes=a,b --xla_disable_hlo_passes=c \
+             --xla_gpu_disable_async_collectives=allreduce,ALLTOALL",
+        )
+        .unwrap();
+        assert_eq!(options.xla_disable_hlo_passes, ["a", "b", "c"]);
+        assert_eq!(
+            options
+                .xla_gpu_disable_async_collectives()
+                .collect::<Vec<_>>(),
+            [CollectiveOpType::Allreduce, CollectiveOpType::Alltoall]
+        );
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert!(matches!(
+            parse("--xla_not_a_flag=1"),
+            Err(Error::UnknownXlaFlag(name)) if name == "xla_not_a_flag"
+        ));
+        assert!(matches!(
+            parse("xla_dump_to=/tmp"),
+            Err(Error::InvalidXlaFlag(_))
+        ));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(matches!(
+            parse("--xla_gpu_autotune_level=4294967297"),
+            Err(Error::InvalidXlaFlag(_))
+        ));
+        assert!(matches!(
+            parse("--xla_cpu_enable_fast_math=yes"),
+            Err(Error::InvalidXlaFlag(_))
+        ));
+    }
+
+    #[test]
+    fn map() {
+        assert!(matches!(
+            parse("--xla_backend_extra_options=a=b"),
+            Err(Error::InvalidXlaFlag(msg)) if msg.contains("map")
+        ));
+    }
+}